@@ -1,4 +1,8 @@
-use clap::Parser;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use clap::{ArgEnum, Parser};
 
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
@@ -7,6 +11,10 @@ pub struct Args {
     #[clap(short, long, default_value_t = 3000)]
     pub port: u16,
 
+    /// Bind address, repeatable: 127.0.0.1, 127.0.0.1:8080, [::1]:8080 or unix:/run/bbox.sock, default 0.0.0.0 with --port
+    #[clap(short, long, multiple_occurrences(true))]
+    pub bind: Vec<Bind>,
+
     /// Read config path
     #[clap(short, long, default_value("./config.json"))]
     pub config: String,
@@ -15,7 +23,114 @@ pub struct Args {
     #[clap(short, long, default_value("./static"))]
     pub scan: String,
 
+    /// Rendered page cache size, 0 disable
+    #[clap(long, default_value_t = 1000)]
+    pub cache_size: usize,
+
+    /// Symlink policy: deny any symlink, root allows targets inside scan directory, follow allows any target
+    #[clap(long, arg_enum, default_value = "root")]
+    pub symlink: SymlinkPolicy,
+
+    /// Output HTML / JSON listing for directory without index.html
+    #[clap(long)]
+    pub listing: bool,
+
+    /// TLS certificate chain PEM file, serve HTTPS with --tls-key
+    #[clap(long, requires = "tls-key")]
+    pub tls_cert: Option<String>,
+
+    /// TLS private key PEM file
+    #[clap(long, requires = "tls-cert")]
+    pub tls_key: Option<String>,
+
+    /// Plain HTTP port redirecting to HTTPS, requires TLS
+    #[clap(long, requires = "tls-cert")]
+    pub redirect_port: Option<u16>,
+
     /// Log output path
     #[clap(short, long, default_value("./logs"))]
     pub log: String,
-}
\ No newline at end of file
+
+    /// Output config.json template and config_doc.txt to current directory and exit
+    #[clap(long)]
+    pub template: bool,
+}
+/// 符号链接策略.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// 拒绝符号链接.
+    Deny,
+    /// 允许指向扫描目录内的符号链接.
+    Root,
+    /// 允许任意符号链接.
+    Follow,
+}
+
+/// 监听地址.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Bind {
+    /// TCP 地址, 未指定端口时使用 --port.
+    Tcp(IpAddr, Option<u16>),
+    /// Unix 域套接字路径.
+    Unix(PathBuf),
+}
+
+impl FromStr for Bind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = value.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err("unix socket path is empty".to_string());
+            }
+            return Ok(Bind::Unix(PathBuf::from(path)));
+        }
+        if let Ok(address) = value.parse::<SocketAddr>() {
+            return Ok(Bind::Tcp(address.ip(), Some(address.port())));
+        }
+        let ip = value.strip_prefix('[').and_then(|it| it.strip_suffix(']')).unwrap_or(value);
+        match ip.parse::<IpAddr>() {
+            Ok(ip) => Ok(Bind::Tcp(ip, None)),
+            Err(_) => Err(format!("invalid bind address '{}'", value))
+        }
+    }
+}
+
+impl fmt::Display for Bind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bind::Tcp(ip, None) => write!(f, "{}", ip),
+            Bind::Tcp(ip, Some(port)) => write!(f, "{}", SocketAddr::new(*ip, *port)),
+            Bind::Unix(path) => write!(f, "unix:{}", path.display())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bind() {
+        let bind = |value: &str| value.parse::<Bind>();
+        assert_eq!(bind("127.0.0.1:8080"), Ok(Bind::Tcp([127, 0, 0, 1].into(), Some(8080))));
+        assert_eq!(bind("127.0.0.1"), Ok(Bind::Tcp([127, 0, 0, 1].into(), None)));
+        assert_eq!(bind("[::1]:8080"), Ok(Bind::Tcp(IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1]), Some(8080))));
+        assert_eq!(bind("[::1]"), Ok(Bind::Tcp(IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1]), None)));
+        assert_eq!(bind("::"), Ok(Bind::Tcp(IpAddr::from([0u16; 8]), None)));
+        assert_eq!(bind("unix:/run/bbox.sock"), Ok(Bind::Unix(PathBuf::from("/run/bbox.sock"))));
+        assert!(bind("unix:").is_err());
+        assert!(bind("localhost:8080").is_err());
+        assert!(bind("127.0.0.1:http").is_err());
+    }
+
+    #[test]
+    fn parse_args() {
+        let args = Args::try_parse_from(["bbox", "-b", "127.0.0.1", "--bind", "[::1]:8080", "-b", "unix:bbox.sock"]).unwrap();
+        assert_eq!(args.bind.len(), 3);
+        assert_eq!(args.bind[2].to_string(), "unix:bbox.sock");
+        assert!(Args::try_parse_from(["bbox", "--bind", "localhost"]).is_err());
+        assert!(Args::try_parse_from(["bbox"]).unwrap().bind.is_empty());
+        assert!(Args::try_parse_from(["bbox", "--template"]).unwrap().template);
+    }
+}
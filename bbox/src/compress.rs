@@ -0,0 +1,127 @@
+use async_compression::Level;
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder, ZlibEncoder};
+use hyper::Body;
+use tokio::io::AsyncBufRead;
+use tokio_util::io::ReaderStream;
+
+/// 支持实时压缩的编码.
+pub const ENCODING_LIST: [Encoding; 3] = [Encoding::Brotli, Encoding::Gzip, Encoding::Deflate];
+
+/// 小于该大小的内容不压缩.
+pub const COMPRESS_MIN_SIZE: u64 = 1024;
+
+/// 内容编码.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    /// Content-Encoding 名称.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    /// 预压缩文件后缀.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gz",
+            Encoding::Deflate => "zz",
+        }
+    }
+
+    /// 压缩流.
+    pub fn encode<R: AsyncBufRead + Send + 'static>(&self, reader: R) -> Body {
+        match self {
+            Encoding::Brotli => Body::wrap_stream(ReaderStream::new(BrotliEncoder::with_quality(reader, Level::Precise(4)))),
+            Encoding::Gzip => Body::wrap_stream(ReaderStream::new(GzipEncoder::new(reader))),
+            // HTTP deflate 是 zlib 格式 (RFC 1950), 不是原始 deflate 数据
+            Encoding::Deflate => Body::wrap_stream(ReaderStream::new(ZlibEncoder::new(reader))),
+        }
+    }
+}
+
+/// 根据 Accept-Encoding 从候选编码中选择, q 值相同时按候选顺序优先.
+pub fn accept_encoding(value: &str, encoding_list: &[Encoding]) -> Option<Encoding> {
+    let mut wildcard = None;
+    let mut quality_list = vec![];
+    for it in value.split(',') {
+        let mut params = it.split(';');
+        let name = params.next().unwrap_or_default().trim().to_lowercase();
+        let quality = params
+            .filter_map(|it| it.trim().strip_prefix("q=").or_else(|| it.trim().strip_prefix("Q=")))
+            .find_map(|it| it.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if name == "*" {
+            wildcard = Some(quality);
+        } else {
+            quality_list.push((name, quality));
+        }
+    }
+    let mut result: Option<(Encoding, f32)> = None;
+    for &encoding in encoding_list {
+        let quality = quality_list.iter()
+            .find(|(name, _)| name == encoding.name() || (encoding == Encoding::Gzip && name == "x-gzip"))
+            .map(|(_, quality)| *quality)
+            .or(wildcard)
+            .unwrap_or(0.0);
+        if quality > 0.0 && result.is_none_or(|(_, value)| quality > value) {
+            result = Some((encoding, quality));
+        }
+    }
+    result.map(|(encoding, _)| encoding)
+}
+
+/// 是否是可压缩的内容类型.
+pub fn is_compressible(content_type: &str) -> bool {
+    let content_type = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    content_type.starts_with("text/")
+        || content_type.ends_with("javascript")
+        || content_type.ends_with("json")
+        || content_type.ends_with("xml")
+        || content_type == "image/svg+xml"
+        || content_type == "application/wasm"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiate() {
+        assert_eq!(accept_encoding("gzip, deflate, br", &ENCODING_LIST), Some(Encoding::Brotli));
+        assert_eq!(accept_encoding("gzip;q=1.0, br;q=0.5", &ENCODING_LIST), Some(Encoding::Gzip));
+        assert_eq!(accept_encoding("br;q=0, *", &ENCODING_LIST), Some(Encoding::Gzip));
+        assert_eq!(accept_encoding("deflate", &ENCODING_LIST), Some(Encoding::Deflate));
+        assert_eq!(accept_encoding("identity", &ENCODING_LIST), None);
+        assert_eq!(accept_encoding("*;q=0", &ENCODING_LIST), None);
+        assert_eq!(accept_encoding("", &ENCODING_LIST), None);
+        assert_eq!(accept_encoding("gzip, deflate, br", &[Encoding::Gzip]), Some(Encoding::Gzip));
+        assert_eq!(accept_encoding("deflate", &[Encoding::Brotli, Encoding::Gzip]), None);
+    }
+
+    #[tokio::test]
+    async fn deflate_zlib() {
+        let body = Encoding::Deflate.encode(std::io::Cursor::new(b"hello hello hello".to_vec()));
+        let bytes = hyper::body::to_bytes(body).await.unwrap();
+        assert_eq!(bytes[0], 0x78);
+        assert_eq!(((bytes[0] as u16) << 8 | bytes[1] as u16) % 31, 0);
+    }
+
+    #[test]
+    fn compressible() {
+        assert!(is_compressible("text/html"));
+        assert!(is_compressible("application/javascript; charset=utf-8"));
+        assert!(is_compressible("application/json"));
+        assert!(is_compressible("image/svg+xml"));
+        assert!(!is_compressible("image/png"));
+        assert!(!is_compressible("video/mp4"));
+    }
+}
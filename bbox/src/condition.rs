@@ -0,0 +1,96 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs::Metadata;
+use std::hash::Hasher;
+use std::time::{SystemTime, UNIX_EPOCH};
+use hyper::HeaderMap;
+use hyper::header::{HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+
+/// 根据文件大小和修改时间生成 ETag.
+pub fn file_etag(metadata: &Metadata) -> String {
+    let modified = metadata.modified().ok()
+        .and_then(|it| it.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    format!(r#""{:x}-{:x}""#, metadata.len(), modified.as_nanos())
+}
+
+/// 根据内容哈希生成 ETag.
+pub fn content_etag(content: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(content);
+    format!(r#""{:x}-{:x}""#, content.len(), hasher.finish())
+}
+
+/// 压缩内容使用单独的 ETag.
+pub fn encoded_etag(etag: &str, encoding: &str) -> String {
+    format!(r#"{}-{}""#, etag.trim_end_matches('"'), encoding)
+}
+
+/// 去掉弱校验前缀.
+fn weak_etag(value: &str) -> &str {
+    value.trim().trim_start_matches("W/")
+}
+
+/// 是否可以返回 304, If-None-Match 存在时忽略 If-Modified-Since.
+pub fn is_not_modified(headers: &HeaderMap<HeaderValue>, etag: &str, modified: Option<SystemTime>) -> bool {
+    if let Some(value) = headers.get(IF_NONE_MATCH).and_then(|it| it.to_str().ok()) {
+        return value.split(',').any(|it| it.trim() == "*" || weak_etag(it) == weak_etag(etag));
+    }
+    match (headers.get(IF_MODIFIED_SINCE).and_then(|it| it.to_str().ok()), modified) {
+        (Some(value), Some(modified)) => match httpdate::parse_http_date(value.trim()) {
+            Ok(date) => modified.duration_since(date).map_or(true, |it| it.as_secs() == 0),
+            Err(_) => false
+        },
+        _ => false
+    }
+}
+
+/// If-Range 校验, ETag 使用强比较, 日期需要与文件修改时间一致.
+pub fn if_range(value: &str, etag: &str, modified: Option<SystemTime>) -> bool {
+    let value = value.trim();
+    if value.starts_with('"') {
+        return value == etag;
+    }
+    if value.starts_with("W/") {
+        return false;
+    }
+    match (httpdate::parse_http_date(value), modified) {
+        (Ok(date), Some(modified)) => httpdate::fmt_http_date(date) == httpdate::fmt_http_date(modified),
+        _ => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn not_modified_by_etag() {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static(r#""a", W/"b""#));
+        assert!(is_not_modified(&headers, r#""b""#, None));
+        assert!(!is_not_modified(&headers, r#""c""#, None));
+        // If-None-Match 优先于 If-Modified-Since
+        headers.insert(IF_MODIFIED_SINCE, HeaderValue::from_static("Sun, 18 Oct 2026 00:00:00 GMT"));
+        assert!(!is_not_modified(&headers, r#""c""#, Some(UNIX_EPOCH)));
+    }
+
+    #[test]
+    fn not_modified_by_date() {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_MODIFIED_SINCE, HeaderValue::from_static("Thu, 01 Jan 1970 00:01:40 GMT"));
+        assert!(is_not_modified(&headers, "", Some(UNIX_EPOCH + Duration::from_millis(100_500))));
+        assert!(is_not_modified(&headers, "", Some(UNIX_EPOCH + Duration::from_secs(50))));
+        assert!(!is_not_modified(&headers, "", Some(UNIX_EPOCH + Duration::from_secs(101))));
+        assert!(!is_not_modified(&headers, "", None));
+    }
+
+    #[test]
+    fn if_range_validator() {
+        let modified = Some(UNIX_EPOCH + Duration::from_secs(100));
+        assert!(if_range(r#""a""#, r#""a""#, modified));
+        assert!(!if_range(r#"W/"a""#, r#""a""#, modified));
+        assert!(if_range("Thu, 01 Jan 1970 00:01:40 GMT", r#""a""#, modified));
+        assert!(!if_range("Thu, 01 Jan 1970 00:01:41 GMT", r#""a""#, modified));
+    }
+}
@@ -1,23 +1,65 @@
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::io::Read;
+use std::ffi::OsStr;
 
-use std::path::{Path, PathBuf};
-use std::string::FromUtf8Error;
+use std::panic::AssertUnwindSafe;
+use std::path::{Component, Path, PathBuf};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 use lazy_static::lazy_static;
 use tokio::io::AsyncReadExt;
-use notify::{Watcher, RecursiveMode, RawEvent, raw_watcher, RecommendedWatcher};
-use tokio::fs::{DirEntry, File};
-use tokio::sync::{RwLock, RwLockReadGuard};
+use notify::RecursiveMode;
+use notify::op::Op;
+use tokio::fs::File;
+use tokio::sync::RwLock;
 use visdom::Vis;
+use visdom::types::Elements;
+use regex::Regex;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use hyper::{Client, Uri};
+use hyper::header::HeaderValue;
+use hyper::client::HttpConnector;
+use lru::LruCache;
 use serde::{Deserialize, Serialize};
 use async_recursion::async_recursion;
-use log::info;
+use log::{error, info};
+use crate::{compress, mime, watch};
+use crate::args::SymlinkPolicy;
+use crate::error::Error;
+use crate::listing::ListingEntry;
+use crate::compress::Encoding;
 
 
 lazy_static! {
     static ref CONFIG_DATA: RwLock<Vec<Config>> = RwLock::new(vec![]);
+    static ref CACHE_DATA: RwLock<Vec<ConfigCache>> = RwLock::new(vec![]);
+    static ref MIME_DATA: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+    static ref PROXY_DATA: RwLock<Vec<ConfigProxy>> = RwLock::new(vec![]);
+    static ref INDEX_DATA: RwLock<String> = RwLock::new(String::new());
+    static ref FILE_DATA: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+    static ref PARAM_REGEX: Regex = Regex::new(r"\{([A-Za-z0-9_.]+)\}").unwrap();
+    static ref WORD_REGEX: Regex = Regex::new("[A-Z]+[a-z]*|[a-z]+").unwrap();
+    static ref HTTP_CLIENT: Client<HttpConnector> = Client::new();
+    static ref PAGE_CACHE: Mutex<LruCache<PageKey, String>> = Mutex::new(LruCache::unbounded());
 }
 
+/// 配置版本, 每次加载配置递增.
+static CONFIG_GENERATION: AtomicUsize = AtomicUsize::new(0);
+/// 首页模板版本, 每次加载首页模板递增.
+static INDEX_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// 页面缓存键: 配置版本, 首页模板版本, 请求路径.
+type PageKey = (usize, usize, String);
+
+/// 远程 SEO 数据默认超时时间, 毫秒.
+const REMOTE_TIMEOUT: u64 = 3000;
+/// 反向代理默认超时时间, 毫秒.
+const PROXY_TIMEOUT: u64 = 30000;
+/// URL 参数编码保留字符.
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~').remove(b'/');
+
 /// 资源核心类
 #[derive(Clone)]
 pub struct Resource {
@@ -27,117 +69,1084 @@ pub struct Resource {
     pub config_path: String,
     /// 配置.
     config: &'static RwLock<Vec<Config>>,
+    /// 缓存策略.
+    cache: &'static RwLock<Vec<ConfigCache>>,
+    /// 自定义内容类型.
+    mime: &'static RwLock<HashMap<String, String>>,
+    /// 反向代理.
+    proxy: &'static RwLock<Vec<ConfigProxy>>,
+    /// 首页模板.
+    index_data: &'static RwLock<String>,
+    /// 静态资源文件索引.
+    file_list: &'static RwLock<HashSet<String>>,
+    /// 渲染页面缓存.
+    page_cache: &'static Mutex<LruCache<PageKey, String>>,
+    /// 渲染页面缓存数量, 0 不缓存.
+    cache_size: usize,
+    /// 符号链接策略.
+    symlink: SymlinkPolicy,
+    /// 是否输出目录列表.
+    pub listing: bool,
 }
 
 impl Resource {
     /// 创建资源核心.
-    pub async fn from(local_path: &str, config_path: &str) -> Resource {
-        let resource = Resource {
+    pub async fn from(local_path: &str, config_path: &str, cache_size: usize, symlink: SymlinkPolicy,
+                      listing: bool) -> Resource {
+        if let Some(value) = NonZeroUsize::new(cache_size) {
+            PAGE_CACHE.lock().unwrap().resize(value);
+        }
+        Resource {
             local_path: local_path.to_string(),
             config_path: config_path.to_string(),
             config: &CONFIG_DATA,
-        };
-        resource
+            cache: &CACHE_DATA,
+            mime: &MIME_DATA,
+            proxy: &PROXY_DATA,
+            index_data: &INDEX_DATA,
+            file_list: &FILE_DATA,
+            page_cache: &PAGE_CACHE,
+            cache_size,
+            symlink,
+            listing,
+        }
     }
 
-    /// 初始化配置.
-    pub async fn init_config(&self) {
+    /// 初始化配置, 配置错误时保留原配置.
+    pub async fn init_config(&self) -> Result<(), Error> {
         let config_file_info = FileInfo::from(&self.config_path).await;
         if config_file_info.exist() {
-            let config_list = serde_json::from_str(&config_file_info.read_string().await.unwrap()).unwrap();
-            *self.config.write().await = config_list;
+            let config_file = Resource::load_config(&config_file_info).await.map_err(|e| Error::Config {
+                path: config_file_info.path_string.clone(),
+                source: Box::new(e),
+            })?;
+            *self.config.write().await = config_file.seo;
+            *self.cache.write().await = config_file.cache;
+            *self.mime.write().await = config_file.mime.into_iter()
+                .map(|(key, value)| (key.trim_start_matches('.').to_lowercase(), value)).collect();
+            *self.proxy.write().await = config_file.proxy;
         } else {
             *self.config.write().await = vec![];
+            *self.cache.write().await = vec![];
+            self.mime.write().await.clear();
+            self.proxy.write().await.clear();
         }
+        self.clear_cache(&CONFIG_GENERATION);
+        Ok(())
     }
 
-    /// 判断是否是静态资源文件.
-    pub async fn is_static_file(&self, url: &str) -> Option<PathBuf> {
-        let url = String::from(url)[1..].to_owned();
-        if url.is_empty() {
+    /// 读取并校验配置文件.
+    async fn load_config(config_file_info: &FileInfo) -> Result<ConfigFile, Error> {
+        let text = config_file_info.read_string().await?;
+        let mut config_file: ConfigFile = if text.trim_start().starts_with('[') {
+            ConfigFile { seo: serde_json::from_str(&text)?, ..ConfigFile::default() }
+        } else {
+            serde_json::from_str(&text)?
+        };
+        for (index, it) in config_file.seo.iter_mut().enumerate() {
+            it.compile().map_err(|e| Error::Regular {
+                section: "seo",
+                index,
+                path: it.path.clone().unwrap_or_default(),
+                source: e,
+            })?;
+            if !it.is_valid() {
+                return Err(Error::Remote { index, remote: it.remote.clone().unwrap_or_default() });
+            }
+            for head in it.heads.iter().flatten() {
+                check_html(head).map_err(|e| Error::Head { index, head: head.clone(), source: Box::new(e) })?;
+            }
+        }
+        for (index, it) in config_file.cache.iter_mut().enumerate() {
+            it.compile().map_err(|e| Error::Regular {
+                section: "cache",
+                index,
+                path: it.path.clone().unwrap_or_default(),
+                source: e,
+            })?;
+        }
+        for (key, value) in &config_file.mime {
+            if HeaderValue::from_str(value).is_err() {
+                return Err(Error::Mime { extension: key.clone(), value: value.clone() });
+            }
+        }
+        for (index, it) in config_file.proxy.iter().enumerate() {
+            if !it.is_valid() {
+                return Err(Error::Upstream { index, upstream: it.upstream.clone() });
+            }
+        }
+        Ok(config_file)
+    }
+
+    /// 初始化首页模板, 读取或解析失败时保留原模板, 避免部署时写入一半的文件替换模板.
+    pub async fn init_index(&self) {
+        let index_file_info = FileInfo::from_vec(vec![&self.local_path, "index.html"]).await;
+        if index_file_info.is_file() {
+            info!("Load index template '{}'", index_file_info.path_string);
+            let value = match index_file_info.read_string().await {
+                Ok(value) => value,
+                Err(e) => {
+                    error!("Index template '{}' read error: {}", index_file_info.path_string, e);
+                    return;
+                }
+            };
+            if let Err(e) = check_html(&value) {
+                error!("Index template '{}' {}", index_file_info.path_string, e);
+                return;
+            }
+            *self.index_data.write().await = value;
+        } else {
+            *self.index_data.write().await = String::new();
+        }
+        self.clear_cache(&INDEX_GENERATION);
+    }
+
+    /// 递增版本并清空页面缓存.
+    fn clear_cache(&self, generation: &AtomicUsize) {
+        generation.fetch_add(1, Ordering::SeqCst);
+        self.page_cache.lock().unwrap().clear();
+    }
+
+    /// 初始化静态资源文件索引.
+    pub async fn init_files(&self) {
+        let root = Path::new(&self.local_path);
+        let mut uri_list = HashSet::new();
+        for it in Resource::scan_directory(root).await {
+            if let Some(uri) = Resource::path_to_uri(root, &it) {
+                uri_list.insert(uri);
+            }
+        }
+        info!("Scan {} static file(s) in '{}'", uri_list.len(), self.local_path);
+        *self.file_list.write().await = uri_list;
+    }
+
+    /// 监听扫描目录和配置文件变化.
+    pub fn watch(&self) {
+        let resource = self.clone();
+        let handle = tokio::runtime::Handle::current();
+        let mut path_list = vec![];
+        let local_path = match std::fs::canonicalize(&self.local_path) {
+            Ok(value) => {
+                path_list.push((value.clone(), RecursiveMode::Recursive));
+                Some(value)
+            }
+            Err(e) => {
+                error!("Watch '{}' error: {}", self.local_path, e);
+                None
+            }
+        };
+        let config_path = watch::file_path(&self.config_path);
+        if let Some((directory, _)) = &config_path {
+            path_list.push((directory.clone(), RecursiveMode::NonRecursive));
+        }
+        watch::spawn(path_list, move |path, op, _| {
+            if config_path.as_ref().is_some_and(|(_, config_file)| config_file == &path) {
+                match handle.block_on(resource.init_config()) {
+                    Ok(_) => info!("Reload config '{}'", resource.config_path),
+                    Err(e) => error!("{}", e)
+                }
+                return;
+            }
+            if let Some(root) = &local_path {
+                handle.block_on(resource.update_file(root, op, &path));
+            }
+        });
+    }
+
+    /// 更新文件.
+    async fn update_file(&self, root: &Path, op: Op, path: &Path) {
+        let uri = match Resource::path_to_uri(root, path) {
+            None => return,
+            Some(value) => value
+        };
+        if op.contains(Op::RESCAN) {
+            self.init_files().await;
+            self.init_index().await;
+            return;
+        }
+        if op.intersects(Op::CREATE | Op::REMOVE | Op::RENAME) {
+            match std::fs::symlink_metadata(path).map(|it| it.file_type()) {
+                Ok(file_type) if file_type.is_dir() => {
+                    let mut uri_list = vec![];
+                    for it in Resource::scan_directory(path).await {
+                        if let Some(value) = Resource::path_to_uri(root, &it) {
+                            uri_list.push(value);
+                        }
+                    }
+                    self.file_list.write().await.extend(uri_list);
+                }
+                Ok(_) => {
+                    self.file_list.write().await.insert(uri.clone());
+                }
+                Err(_) => {
+                    let directory = format!("{}/", uri);
+                    self.file_list.write().await.retain(|it| it != &uri && !it.starts_with(&directory));
+                }
+            }
+        }
+        if uri == "index.html" {
+            self.init_index().await;
+        }
+    }
+
+    /// 扫描目录, 符号链接不跟随, 作为文件记录并在请求时按符号链接策略解析.
+    #[async_recursion]
+    async fn scan_directory(local_path: &Path) -> Vec<PathBuf> {
+        let mut path_list = vec![];
+        let mut paths = match tokio::fs::read_dir(local_path).await {
+            Ok(value) => value,
+            Err(_) => return path_list
+        };
+        while let Ok(Some(value)) = paths.next_entry().await {
+            let file_type = match value.file_type().await {
+                Ok(value) => value,
+                Err(_) => continue
+            };
+            if file_type.is_dir() {
+                path_list.append(&mut Resource::scan_directory(&value.path()).await);
+            } else if file_type.is_file() || file_type.is_symlink() {
+                path_list.push(value.path());
+            }
+        }
+        path_list
+    }
+
+    /// 本地路径转换为资源路径.
+    fn path_to_uri(root: &Path, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(root).ok()?;
+        let mut uri_list = vec![];
+        for it in relative.components() {
+            uri_list.push(it.as_os_str().to_str()?);
+        }
+        if uri_list.is_empty() {
             return None;
         }
-        let path = Path::new(&self.local_path).join(url);
-        if path.exists() {
-            return Some(path.to_owned());
+        Some(uri_list.join("/"))
+    }
+
+    /// 按符号链接策略解析扫描目录内的文件路径.
+    async fn resolve_path(&self, uri: &str) -> Result<PathBuf, Error> {
+        let root = tokio::fs::canonicalize(&self.local_path).await?;
+        let path = tokio::fs::canonicalize(root.join(uri)).await?;
+        let allow = match self.symlink {
+            SymlinkPolicy::Deny => path == root.join(uri),
+            SymlinkPolicy::Root => path.starts_with(&root),
+            SymlinkPolicy::Follow => true,
+        };
+        if !allow {
+            return Err(Error::Forbidden(format!("{} -> {}", uri, path.display())));
         }
-        return None;
+        Ok(path)
+    }
+
+    /// 判断是否是静态资源文件, 越界路径返回 Forbidden.
+    pub async fn is_static_file(&self, url: &str, accept_encoding: Option<&str>) -> Result<Option<StaticFile>, Error> {
+        let mut uri = match decode_path(url) {
+            Some(value) => value,
+            None if url.trim_start_matches('/').is_empty() => return Ok(None),
+            None => return Err(Error::Forbidden(url.to_string()))
+        };
+        // 目录请求 /docs/ 使用目录内的 index.html
+        if url.ends_with('/') {
+            uri = format!("{}/index.html", uri);
+        }
+        let file_list = self.file_list.read().await;
+        // 符号链接目录内的文件不在索引中, 按符号链接策略解析
+        let linked = !file_list.contains(&uri);
+        if linked && !uri.match_indices('/').any(|(index, _)| file_list.contains(&uri[..index])) {
+            return Ok(None);
+        }
+        // 预压缩文件 app.js.br / app.js.gz
+        let precompressed: Vec<Encoding> = [Encoding::Brotli, Encoding::Gzip].iter().copied()
+            .filter(|it| file_list.contains(&format!("{}.{}", uri, it.extension())))
+            .collect();
+        drop(file_list);
+        let path = match self.resolve_path(&uri).await {
+            Err(Error::Io(_)) if linked => return Ok(None),
+            result => result?
+        };
+        // 指向目录的符号链接按目录处理
+        if !tokio::fs::metadata(&path).await?.is_file() {
+            return Ok(None);
+        }
+        let encoded = match accept_encoding.and_then(|it| compress::accept_encoding(it, &precompressed)) {
+            None => None,
+            Some(encoding) => self.resolve_path(&format!("{}.{}", uri, encoding.extension())).await.ok()
+                .map(|it| (it, encoding))
+        };
+        let extension = Path::new(&uri).extension().and_then(OsStr::to_str).unwrap_or_default();
+        let content_type = mime::content_type(extension, &*self.mime.read().await);
+        Ok(Some(StaticFile {
+            path,
+            content_type,
+            precompressed: !precompressed.is_empty(),
+            encoded,
+        }))
+    }
+
+    /// 判断是否是目录.
+    pub async fn is_directory(&self, url: &str) -> Option<Directory> {
+        let uri = if url == "/" { String::new() } else { decode_path(url)? };
+        let path = if uri.is_empty() {
+            tokio::fs::canonicalize(&self.local_path).await.ok()?
+        } else {
+            self.resolve_path(&uri).await.ok()?
+        };
+        if !tokio::fs::metadata(&path).await.ok()?.is_dir() {
+            return None;
+        }
+        let index = match uri.is_empty() {
+            true => "index.html".to_string(),
+            false => format!("{}/index.html", uri)
+        };
+        let index = self.file_list.read().await.contains(&index);
+        Some(Directory { uri, path, index })
+    }
+
+    /// 读取目录列表, 忽略隐藏文件和不符合符号链接策略的文件.
+    pub async fn list_directory(&self, directory: &Directory) -> Vec<ListingEntry> {
+        let mut entry_list = vec![];
+        let mut paths = match tokio::fs::read_dir(&directory.path).await {
+            Ok(value) => value,
+            Err(_) => return entry_list
+        };
+        while let Ok(Some(value)) = paths.next_entry().await {
+            let name = match value.file_name().into_string() {
+                Ok(value) if !value.starts_with('.') => value,
+                _ => continue
+            };
+            let uri = match directory.uri.is_empty() {
+                true => name.clone(),
+                false => format!("{}/{}", directory.uri, name)
+            };
+            let metadata = match self.resolve_path(&uri).await {
+                Err(_) => continue,
+                Ok(path) => match tokio::fs::metadata(path).await {
+                    Ok(value) => value,
+                    Err(_) => continue
+                }
+            };
+            entry_list.push(ListingEntry {
+                name,
+                directory: metadata.is_dir(),
+                size: if metadata.is_dir() { 0 } else { metadata.len() },
+                modified: metadata.modified().ok().map(httpdate::fmt_http_date),
+            });
+        }
+        entry_list.sort_by(|a, b| b.directory.cmp(&a.directory).then_with(|| a.name.cmp(&b.name)));
+        entry_list
     }
 
     /// 是否有首页.
     pub async fn is_index(&self) -> bool {
-        false
+        !self.index_data.read().await.is_empty()
+    }
+
+    /// 缓存策略, 未配置时内容哈希文件缓存一年, 其余每次校验.
+    pub async fn cache_control(&self, uri: &str, static_file: bool) -> String {
+        if let Some(value) = ConfigCache::find(&self.cache.read().await, uri) {
+            return value.control.clone();
+        }
+        if static_file && is_hashed_file(uri) {
+            return "public, max-age=31536000, immutable".to_string();
+        }
+        "no-cache".to_string()
+    }
+
+    /// 查找请求路径对应的反向代理, 拒绝包含 . 或 .. 路径段的请求, 避免绕过前缀匹配.
+    pub async fn find_proxy(&self, uri: &str) -> Result<Option<ConfigProxy>, Error> {
+        check_path(uri)?;
+        Ok(ConfigProxy::find(&self.proxy.read().await, uri).cloned())
+    }
+
+    /// 根据扩展名获取内容类型.
+    pub async fn content_type(&self, extension: &str) -> String {
+        mime::content_type(extension, &*self.mime.read().await)
     }
 
     /// 根据配置解析首页模板.
-    pub async fn parse_html_by_config(&self, uri: &str) -> String {
-        let html = "".to_string();
-        let config_list = &*self.config.read().await;
-        let config = config_list.iter().find(|it| {
-            match it.path.as_ref() {
-                None => false,
-                Some(value) => {
-                    if value.starts_with("pre://") && value[6..].starts_with(&uri[1..]) {
-                        return true;
-                    }
-                    if value.starts_with("tail://") && value[6..].ends_with(&uri[1..]) {
-                        return true;
+    pub async fn parse_html_by_config(&self, uri: &str, query: Option<&str>) -> String {
+        // 先读取版本, 渲染期间重新加载只会让本次缓存失效
+        let generation = (CONFIG_GENERATION.load(Ordering::SeqCst), INDEX_GENERATION.load(Ordering::SeqCst));
+        let html = self.index_data.read().await.clone();
+        let config_list: Vec<Config> = Config::find_all(&self.config.read().await, uri).into_iter().cloned().collect();
+
+        // 远程 SEO 数据不缓存, 引用查询参数时缓存键包含查询参数
+        let key = if self.cache_size == 0 || config_list.iter().any(|it| it.remote.is_some()) {
+            None
+        } else if config_list.iter().any(Config::use_query) {
+            Some((generation.0, generation.1, format!("{}?{}", uri, query.unwrap_or_default())))
+        } else {
+            Some((generation.0, generation.1, uri.to_string()))
+        };
+        if let Some(key) = &key {
+            if let Some(value) = self.page_cache.lock().unwrap().get(key) {
+                return value.clone();
+            }
+        }
+
+        let html = match Config::build(&config_list, uri, query).await {
+            None => html,
+            Some(config) => config.render(&html).unwrap_or_else(|e| {
+                error!("Render '{}' error: {}", uri, e);
+                html
+            })
+        };
+        if let Some(key) = key {
+            self.page_cache.lock().unwrap().put(key, html.clone());
+        }
+        html
+    }
+}
+
+/// 配置文件, 兼容仅包含 SEO 配置的数组格式.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ConfigFile {
+    /// SEO 配置.
+    #[serde(default)]
+    pub seo: Vec<Config>,
+    /// 缓存策略.
+    #[serde(default)]
+    pub cache: Vec<ConfigCache>,
+    /// 自定义内容类型, 扩展名对应内容类型.
+    #[serde(default)]
+    pub mime: HashMap<String, String>,
+    /// 反向代理.
+    #[serde(default)]
+    pub proxy: Vec<ConfigProxy>,
+}
+
+/// 静态资源文件.
+pub struct StaticFile {
+    /// 文件路径.
+    pub path: PathBuf,
+    /// 内容类型, 按原文件扩展名确定.
+    pub content_type: String,
+    /// 是否存在预压缩文件.
+    pub precompressed: bool,
+    /// 按 Accept-Encoding 选择的预压缩文件路径和编码.
+    pub encoded: Option<(PathBuf, Encoding)>,
+}
+
+/// 目录.
+pub struct Directory {
+    /// 相对扫描目录的路径, 根目录为空.
+    pub uri: String,
+    /// 目录路径.
+    pub path: PathBuf,
+    /// 目录内是否有 index.html.
+    pub index: bool,
+}
+
+/// SEO 配置.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Config {
+    /// 路径.
+    pub path: Option<String>,
+    /// 资源.
+    pub metas: Option<Vec<ConfigMetas>>,
+    /// 链接.
+    pub links: Option<Vec<ConfigLinks>>,
+    /// 标题.
+    pub title: Option<String>,
+    /// 自定义头部.
+    pub heads: Option<Vec<String>>,
+    /// 远程 SEO 数据地址, 例如 http://api.local/seo?path={path}, 返回 title / metas / links / heads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    /// 远程 SEO 数据超时时间, 毫秒.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_timeout: Option<u64>,
+    /// 编译后的正则路径.
+    #[serde(skip)]
+    pub regular: Option<Regex>,
+}
+
+impl Config {
+    /// 查找全部匹配配置, 按优先级从低到高排序, 同优先级保持配置顺序.
+    pub fn find_all<'a>(config_list: &'a [Config], uri: &str) -> Vec<&'a Config> {
+        let mut result: Vec<(PathMatch, &Config)> = config_list.iter()
+            .filter_map(|it| it.matches(uri).map(|level| (level, it)))
+            .collect();
+        result.sort_by_key(|(level, _)| *level);
+        result.into_iter().map(|(_, it)| it).collect()
+    }
+
+    /// 合并全部匹配配置并替换路径参数和查询参数, 远程 SEO 数据覆盖所属配置, 请求失败时使用配置本身.
+    pub async fn build(config_list: &[Config], uri: &str, query: Option<&str>) -> Option<Config> {
+        let config_list = Config::find_all(config_list, uri);
+        if config_list.is_empty() {
+            return None;
+        }
+        let mut params = HashMap::new();
+        params.insert("path".to_string(), percent_decode_str(uri).decode_utf8_lossy().into_owned());
+        for it in &config_list {
+            params.extend(it.captures(uri));
+        }
+        for it in query.unwrap_or_default().split('&').filter(|it| !it.is_empty()) {
+            let (key, value) = it.split_once('=').unwrap_or((it, ""));
+            params.insert(format!("query.{}", decode_query(key)), decode_query(value));
+        }
+        let mut remote_list = vec![];
+        for it in &config_list {
+            remote_list.push(match &it.remote {
+                None => None,
+                Some(remote) => it.fetch(&interpolate(remote, &params, encode_url)).await
+            });
+        }
+        let mut merge_list = vec![];
+        for (it, remote) in config_list.iter().zip(remote_list.iter()) {
+            merge_list.push(*it);
+            if let Some(remote) = remote {
+                merge_list.push(remote);
+            }
+        }
+        Some(Config::merge(&merge_list).interpolate(&params))
+    }
+
+    /// 远程 SEO 数据地址是否有效, 仅支持 http, 未配置时有效.
+    pub fn is_valid(&self) -> bool {
+        let remote = match &self.remote {
+            None => return true,
+            Some(value) => PARAM_REGEX.replace_all(value, "0")
+        };
+        remote.parse::<Uri>().is_ok_and(|it| it.scheme_str() == Some("http") && it.authority().is_some())
+    }
+
+    /// 是否引用查询参数.
+    pub fn use_query(&self) -> bool {
+        let query = |it: &String| it.contains("{query.");
+        self.title.iter().any(query)
+            || self.metas.iter().flatten().filter_map(|it| it.content.as_ref()).any(query)
+            || self.links.iter().flatten().map(|it| &it.href).any(query)
+            || self.heads.iter().flatten().any(query)
+    }
+
+    /// 请求远程 SEO 数据.
+    async fn fetch(&self, url: &str) -> Option<Config> {
+        let timeout = Duration::from_millis(self.remote_timeout.unwrap_or(REMOTE_TIMEOUT));
+        let result = tokio::time::timeout(timeout, async {
+            let response = HTTP_CLIENT.get(url.parse()?).await?;
+            if !response.status().is_success() {
+                return Err(format!("status {}", response.status()).into());
+            }
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            let config: Config = serde_json::from_slice(&body)?;
+            for it in config.heads.iter().flatten() {
+                check_html(it)?;
+            }
+            Ok::<Config, Box<dyn std::error::Error + Send + Sync>>(config)
+        }).await;
+        match result {
+            Ok(Ok(value)) => Some(value),
+            Ok(Err(e)) => {
+                error!("Remote '{}' error: {}", url, e);
+                None
+            }
+            Err(_) => {
+                error!("Remote '{}' timeout after {:?}", url, timeout);
+                None
+            }
+        }
+    }
+
+    /// 依次合并配置, 后面的标题和同名资源覆盖前面的, 自定义头部追加.
+    pub fn merge(config_list: &[&Config]) -> Config {
+        let mut result = Config::default();
+        for config in config_list {
+            if config.title.is_some() {
+                result.title = config.title.clone();
+            }
+            if let Some(metas) = &config.metas {
+                let result_metas = result.metas.get_or_insert_with(Vec::new);
+                for it in metas {
+                    match result_metas.iter_mut().find(|value| value.key() == it.key()) {
+                        None => result_metas.push(it.clone()),
+                        Some(value) => *value = it.clone()
                     }
-                    if value.starts_with("regular") {
-                        return true;
+                }
+            }
+            if let Some(links) = &config.links {
+                let result_links = result.links.get_or_insert_with(Vec::new);
+                for it in links {
+                    match result_links.iter_mut().find(|value| value.key() == it.key()) {
+                        None => result_links.push(it.clone()),
+                        Some(value) => *value = it.clone()
                     }
-                    false
                 }
             }
-        });
-        if config.is_none() {
-            return html;
+            if let Some(heads) = &config.heads {
+                result.heads.get_or_insert_with(Vec::new).extend(heads.iter().cloned());
+            }
         }
+        result
+    }
 
-        let config = config.unwrap();
-        let root = Vis::load(&html).unwrap();
-        // 标题内容
-        if config.title.is_some() {
-            root.find("head > title").set_text(config.title.as_ref().unwrap());
+    /// 匹配请求路径.
+    pub fn matches(&self, uri: &str) -> Option<PathMatch> {
+        match_path(self.path.as_deref(), self.regular.as_ref(), uri)
+    }
+
+    /// 将配置渲染到首页模板.
+    pub fn render(&self, html: &str) -> Result<String, Error> {
+        let root = load_html(html)?;
+        let mut head = root.find("head");
+        // 标题内容, 由 set_text 转义
+        if let Some(title) = &self.title {
+            let mut title_element = head.children("title");
+            if title_element.is_empty() {
+                head.prepend(&mut load_html("<title></title>")?);
+                title_element = head.children("title");
+            }
+            title_element.set_text(title);
         }
         // 资源
-        if config.metas.is_some() {
+        if let Some(metas) = &self.metas {
+            let mut heads = String::new();
+            for it in metas {
+                let (attribute, value) = match it.key() {
+                    None => continue,
+                    Some(key) => key
+                };
+                let mut meta = head.children("meta").filter_by(|_, element| {
+                    element.get_attribute(attribute).is_some_and(|it| it.to_string().to_lowercase() == value)
+                        || (attribute == "charset" && element.has_attribute(attribute))
+                });
+                if meta.is_empty() {
+                    heads.push_str(&it.to_html());
+                } else if let Some(charset) = &it.charset {
+                    meta.set_attr("charset", Some(&escape_html(charset)));
+                } else {
+                    meta.set_attr("content", Some(&escape_html(it.content.as_deref().unwrap_or_default())));
+                }
+            }
+            if !heads.is_empty() {
+                head.append(&mut load_html(&heads)?);
+            }
+        }
+        // 链接
+        if let Some(links) = &self.links {
             let mut heads = String::new();
-            for it in config.metas.as_ref().unwrap() {
-                heads.push_str(&format!(r##"<meta name="{}" content="{}">"##, it.name, it.content))
+            for it in links {
+                let key = it.key();
+                let mut link = head.children("link").filter_by(|_, element| {
+                    let attribute = |name: &str| element.get_attribute(name).map(|it| it.to_string().to_lowercase());
+                    (attribute("rel"), attribute("hreflang"), attribute("type")) == (Some(key.0.clone()), key.1.clone(), key.2.clone())
+                });
+                if link.is_empty() {
+                    heads.push_str(&it.to_html());
+                } else {
+                    link.set_attr("href", Some(&escape_html(&it.href)));
+                }
+            }
+            if !heads.is_empty() {
+                head.append(&mut load_html(&heads)?);
             }
-            root.find("head").append(&mut Vis::load(&heads).unwrap());
         }
-        // 标题
-        if config.heads.is_some() {
+        // 自定义头部, 原样输出不转义
+        if let Some(config_heads) = &self.heads {
             let mut heads = String::new();
-            for it in config.heads.as_ref().unwrap() {
+            for it in config_heads {
                 heads.push_str(&format!("{}\n", it))
             }
-            root.find("head").append(&mut Vis::load(&heads).unwrap());
+            head.append(&mut load_html(&heads)?);
+        }
+        // 输出完整文档, 保留 DOCTYPE 和 html 元素
+        output_html(&root)
+    }
+
+    /// 路径命名参数, 来自正则命名分组或 :param 路径段.
+    pub fn captures(&self, uri: &str) -> Vec<(String, String)> {
+        let regular = match &self.regular {
+            None => return vec![],
+            Some(value) => value
+        };
+        let captures = match regular.captures(uri) {
+            None => return vec![],
+            Some(value) => value
+        };
+        regular.capture_names().flatten()
+            .filter_map(|name| captures.name(name).map(|it| (name.to_string(), percent_decode_str(it.as_str()).decode_utf8_lossy().into_owned())))
+            .collect()
+    }
+
+    /// 替换标题, 资源内容, 链接地址和自定义头部中的 {name} 参数.
+    pub fn interpolate(mut self, params: &HashMap<String, String>) -> Config {
+        self.title = self.title.map(|it| interpolate(&it, params, str::to_string));
+        if let Some(metas) = &mut self.metas {
+            for it in metas {
+                it.content = it.content.as_ref().map(|it| interpolate(it, params, str::to_string));
+            }
+        }
+        if let Some(links) = &mut self.links {
+            for it in links {
+                it.href = interpolate(&it.href, params, str::to_string);
+            }
         }
-        root.html()
+        // 自定义头部原样输出, 参数值需要转义
+        self.heads = self.heads.map(|heads| heads.iter().map(|it| interpolate(it, params, escape_html)).collect());
+        self
+    }
+
+    /// 编译正则路径.
+    pub fn compile(&mut self) -> Result<(), regex::Error> {
+        self.regular = compile_path(self.path.as_deref().unwrap_or_default())?;
+        Ok(())
     }
 }
 
-/// 配置文件.
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Config {
-    /// 路径.
+/// 缓存策略配置.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ConfigCache {
+    /// 路径, 规则与 SEO 配置相同.
     pub path: Option<String>,
-    /// 资源.
-    pub metas: Option<Vec<ConfigMetas>>,
-    /// 标题.
-    pub title: Option<String>,
-    /// 自定义头部.
-    pub heads: Option<Vec<String>>,
+    /// Cache-Control 响应头.
+    pub control: String,
+    /// 编译后的正则路径.
+    #[serde(skip)]
+    pub regular: Option<Regex>,
+}
+
+impl ConfigCache {
+    /// 查找优先级最高的缓存策略, 同优先级后面的覆盖前面的.
+    pub fn find<'a>(cache_list: &'a [ConfigCache], uri: &str) -> Option<&'a ConfigCache> {
+        cache_list.iter()
+            .filter_map(|it| match_path(it.path.as_deref(), it.regular.as_ref(), uri).map(|level| (level, it)))
+            .max_by_key(|(level, _)| *level)
+            .map(|(_, it)| it)
+    }
+
+    /// 编译正则路径.
+    pub fn compile(&mut self) -> Result<(), regex::Error> {
+        self.regular = compile_path(self.path.as_deref().unwrap_or_default())?;
+        Ok(())
+    }
+}
+
+/// 反向代理配置.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigProxy {
+    /// 路径前缀, 按路径段匹配, 例如 /api 匹配 /api 和 /api/user, 不匹配 /apis.
+    pub path: String,
+    /// 上游服务地址, 例如 http://127.0.0.1:8080.
+    pub upstream: String,
+    /// 是否去掉路径前缀.
+    #[serde(default)]
+    pub strip: bool,
+    /// 等待上游响应超时时间, 毫秒.
+    #[serde(default = "ConfigProxy::default_timeout")]
+    pub timeout: u64,
+}
+
+impl ConfigProxy {
+    /// 默认超时时间.
+    fn default_timeout() -> u64 {
+        PROXY_TIMEOUT
+    }
+
+    /// 查找路径前缀最长的反向代理.
+    pub fn find<'a>(proxy_list: &'a [ConfigProxy], uri: &str) -> Option<&'a ConfigProxy> {
+        proxy_list.iter()
+            .filter(|it| it.matches(uri))
+            .max_by_key(|it| it.prefix().len())
+    }
+
+    /// 路径前缀, 不包含末尾斜杠.
+    fn prefix(&self) -> &str {
+        self.path.trim_end_matches('/')
+    }
+
+    /// 是否匹配请求路径.
+    pub fn matches(&self, uri: &str) -> bool {
+        let prefix = self.prefix();
+        uri.strip_prefix(prefix).is_some_and(|it| it.is_empty() || it.starts_with('/'))
+    }
+
+    /// 上游请求地址, 按配置去掉路径前缀.
+    pub fn upstream(&self, uri: &Uri) -> Result<Uri, Error> {
+        check_path(uri.path())?;
+        let mut path = uri.path();
+        if self.strip {
+            path = &path[self.prefix().len()..];
+        }
+        let base = self.upstream.trim_end_matches('/');
+        let value = match (path.starts_with('/'), uri.query()) {
+            (true, None) => format!("{}{}", base, path),
+            (false, None) => format!("{}/{}", base, path),
+            (true, Some(query)) => format!("{}{}?{}", base, path, query),
+            (false, Some(query)) => format!("{}/{}?{}", base, path, query),
+        };
+        Ok(value.parse()?)
+    }
+
+    /// 上游服务地址是否有效, 仅支持 http.
+    pub fn is_valid(&self) -> bool {
+        self.upstream.parse::<Uri>().is_ok_and(|it| it.scheme_str() == Some("http") && it.authority().is_some())
+    }
+}
+
+/// 匹配请求路径, 空路径匹配全部.
+fn match_path(value: Option<&str>, regular: Option<&Regex>, uri: &str) -> Option<PathMatch> {
+    let value = match value {
+        None | Some("") => return Some(PathMatch::CatchAll),
+        Some(value) => value
+    };
+    let path = uri.trim_start_matches('/');
+    if let Some(value) = value.strip_prefix("exact://") {
+        let matched = match regular {
+            None => value.trim_start_matches('/') == path,
+            Some(regular) => regular.is_match(uri)
+        };
+        return matched.then_some(PathMatch::Exact);
+    }
+    if let Some(value) = value.strip_prefix("pre://") {
+        let value = value.trim_start_matches('/');
+        let matched = match regular {
+            None => path.starts_with(value),
+            Some(regular) => regular.is_match(uri)
+        };
+        return matched.then_some(PathMatch::Pre(value.len()));
+    }
+    if let Some(value) = value.strip_prefix("tail://") {
+        return uri.ends_with(value).then_some(PathMatch::Tail(value.len()));
+    }
+    if value.starts_with("regular://") {
+        return regular.filter(|it| it.is_match(uri)).map(|_| PathMatch::Regular);
+    }
+    None
+}
+
+/// 编译正则路径, exact:// 和 pre:// 包含 :param 路径段时也编译为正则.
+fn compile_path(path: &str) -> Result<Option<Regex>, regex::Error> {
+    if let Some(value) = path.strip_prefix("regular://") {
+        Regex::new(value).map(Some)
+    } else if let Some(value) = path.strip_prefix("exact://") {
+        compile_segments(value, true)
+    } else if let Some(value) = path.strip_prefix("pre://") {
+        compile_segments(value, false)
+    } else {
+        Ok(None)
+    }
+}
+
+/// 将包含 :param 的路径编译为正则.
+fn compile_segments(value: &str, exact: bool) -> Result<Option<Regex>, regex::Error> {
+    let value = value.trim_start_matches('/');
+    if !value.split('/').any(|it| it.starts_with(':')) {
+        return Ok(None);
+    }
+    let segments: Vec<String> = value.split('/').map(|it| match it.strip_prefix(':') {
+        None => regex::escape(it),
+        Some(name) => format!("(?P<{}>[^/]+)", name)
+    }).collect();
+    let pattern = format!("^/{}{}", segments.join("/"), if exact { "$" } else { "" });
+    Regex::new(&pattern).map(Some)
+}
+
+/// 解码请求路径为扫描目录内的相对路径, 包含 .. 等越界路径段返回 None.
+fn decode_path(url: &str) -> Option<String> {
+    let value = percent_decode_str(url).decode_utf8().ok()?;
+    let mut segment_list = vec![];
+    for it in value.split('/') {
+        match it {
+            "" | "." => continue,
+            _ if is_unsafe_segment(it) => return None,
+            _ => segment_list.push(it),
+        }
+    }
+    let uri = segment_list.join("/");
+    if uri.is_empty() || !Path::new(&uri).components().all(|it| matches!(it, Component::Normal(_))) {
+        return None;
+    }
+    Some(uri)
+}
+
+/// 路径段是否越界, 包含 .., 反斜杠或空字符.
+fn is_unsafe_segment(segment: &str) -> bool {
+    segment == ".." || segment.contains('\\') || segment.contains('\0')
+}
+
+/// 校验请求路径, 解码后包含 . 或越界路径段时返回错误.
+pub fn check_path(url: &str) -> Result<(), Error> {
+    let value = percent_decode_str(url).decode_utf8_lossy();
+    if value.split('/').any(|it| it == "." || is_unsafe_segment(it)) {
+        return Err(Error::BadPath(url.to_string()));
+    }
+    Ok(())
+}
+
+/// 文件名是否包含内容哈希, 例如 app.3f9a1c.js, index-BsQx1aZ8.js, index-Bs-Qx1aZ.js.
+pub fn is_hashed_file(uri: &str) -> bool {
+    let name = uri.rsplit('/').next().unwrap_or_default();
+    let stem = match name.rsplit_once('.') {
+        None => return false,
+        Some((stem, _)) => stem
+    };
+    // 哈希位于最后一段, 以 . 或 - 分隔, base64url 哈希本身可能包含 -
+    let (dotted, segment) = match stem.rsplit_once('.') {
+        None => (false, stem),
+        Some((_, segment)) => (true, segment)
+    };
+    let mut hash_list: Vec<&str> = segment.match_indices('-').map(|(index, _)| &segment[index + 1..]).collect();
+    if dotted {
+        hash_list.push(segment);
+    }
+    hash_list.into_iter().any(is_hash)
+}
+
+/// 是否为打包工具生成的哈希: 含数字的 6 位以上十六进制, 或 8 位以上不像单词的 base64url 字符.
+fn is_hash(value: &str) -> bool {
+    if !value.chars().all(|it| it.is_ascii_alphanumeric() || it == '-' || it == '_') {
+        return false;
+    }
+    if value.len() >= 6 && value.chars().all(|it| it.is_ascii_hexdigit()) && value.chars().any(|it| it.is_ascii_digit()) {
+        return true;
+    }
+    value.len() >= 8 && !value.split(['-', '_']).any(|it| it.len() >= 4 && is_word(it))
+}
+
+/// 是否像单词, 全小写, 全大写或每个单词至少 3 个字母的驼峰命名, 例如 vendors, Settings, UserProfile.
+fn is_word(value: &str) -> bool {
+    value.chars().all(|it| it.is_ascii_alphabetic()) && WORD_REGEX.find_iter(value).all(|it| it.as_str().len() >= 3)
+}
+
+/// 替换 {name} 参数, 参数值经过 encode 处理, 不存在的参数保持原样.
+fn interpolate(value: &str, params: &HashMap<String, String>, encode: fn(&str) -> String) -> String {
+    PARAM_REGEX.replace_all(value, |captures: &regex::Captures| {
+        match params.get(&captures[1]) {
+            None => captures[0].to_string(),
+            Some(value) => encode(value)
+        }
+    }).into_owned()
+}
+
+/// 编码 URL 参数.
+pub fn encode_url(value: &str) -> String {
+    utf8_percent_encode(value, URL_ENCODE_SET).to_string()
+}
+
+/// 解码 URL 查询参数.
+fn decode_query(value: &str) -> String {
+    percent_decode_str(&value.replace('+', " ")).decode_utf8_lossy().into_owned()
+}
+
+/// 解析 HTML, 未闭合的脚本, 注释等返回错误.
+fn load_html(html: &str) -> Result<Elements<'_>, Error> {
+    Vis::load(html).map_err(|e| Error::Html(e.to_string()))
+}
+
+/// 输出完整文档, 截断的标签在输出时会 panic, 转换为错误.
+fn output_html(root: &Elements<'_>) -> Result<String, Error> {
+    std::panic::catch_unwind(AssertUnwindSafe(|| root.outer_html()))
+        .map_err(|_| Error::Html("unfinished tag".to_string()))
+}
+
+/// 校验 HTML 能否完整解析和输出.
+fn check_html(html: &str) -> Result<(), Error> {
+    output_html(&load_html(html)?).map(|_| ())
+}
+
+/// 转义 HTML 文本和属性值.
+pub fn escape_html(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for it in value.chars() {
+        match it {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(it)
+        }
+    }
+    result
+}
+
+/// 路径匹配优先级, 越靠后优先级越高.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathMatch {
+    /// 空路径匹配全部.
+    CatchAll,
+    /// 正则匹配 regular://.
+    Regular,
+    /// 后缀匹配 tail://, 越长优先级越高.
+    Tail(usize),
+    /// 前缀匹配 pre://, 越长优先级越高.
+    Pre(usize),
+    /// 精确匹配 exact://.
+    Exact,
 }
 
 /// 配置资源说明.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ConfigMetas {
-    pub name: String,
-    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub property: Option<String>,
+    #[serde(rename = "http-equiv", skip_serializing_if = "Option::is_none")]
+    pub http_equiv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub itemprop: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+impl ConfigMetas {
+    /// 去重使用的属性名和小写属性值, charset 全局唯一.
+    pub fn key(&self) -> Option<(&'static str, String)> {
+        if self.charset.is_some() {
+            return Some(("charset", String::new()));
+        }
+        [("name", &self.name), ("property", &self.property), ("http-equiv", &self.http_equiv), ("itemprop", &self.itemprop)]
+            .iter()
+            .find_map(|(attribute, value)| value.as_ref().map(|it| (*attribute, it.to_lowercase())))
+    }
+
+    /// 生成 meta 标签.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from("<meta");
+        for (attribute, value) in [("name", &self.name), ("property", &self.property), ("http-equiv", &self.http_equiv),
+            ("itemprop", &self.itemprop), ("charset", &self.charset), ("content", &self.content)] {
+            if let Some(value) = value {
+                html.push_str(&format!(r##" {}="{}""##, attribute, escape_html(value)));
+            }
+        }
+        html.push('>');
+        html
+    }
+}
+
+/// 配置链接说明.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ConfigLinks {
+    pub rel: String,
+    pub href: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hreflang: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub link_type: Option<String>,
+}
+
+impl ConfigLinks {
+    /// 去重使用的 rel, hreflang, type 小写值.
+    pub fn key(&self) -> (String, Option<String>, Option<String>) {
+        (self.rel.to_lowercase(), self.hreflang.as_ref().map(|it| it.to_lowercase()), self.link_type.as_ref().map(|it| it.to_lowercase()))
+    }
+
+    /// 生成 link 标签.
+    pub fn to_html(&self) -> String {
+        let mut html = format!(r##"<link rel="{}" href="{}""##, escape_html(&self.rel), escape_html(&self.href));
+        for (attribute, value) in [("hreflang", &self.hreflang), ("type", &self.link_type)] {
+            if let Some(value) = value {
+                html.push_str(&format!(r##" {}="{}""##, attribute, escape_html(value)));
+            }
+        }
+        html.push('>');
+        html
+    }
 }
 
 /// 文件信息类.
@@ -151,7 +1160,10 @@ pub struct FileInfo {
 impl FileInfo {
     /// 创建文件信息.
     pub async fn from(path: &str) -> Self {
-        let mut path_buf = PathBuf::from(&FileInfo::parse_os_prefix(path));
+        let mut path_buf = match Path::new(path).is_absolute() {
+            true => PathBuf::from(path),
+            false => PathBuf::from(&FileInfo::parse_os_prefix(path))
+        };
         if path_buf.is_relative() {
             let path = path_buf.as_os_str().to_owned();
             path_buf = env::current_dir().unwrap();
@@ -166,8 +1178,13 @@ impl FileInfo {
     /// 创建文件信息.
     pub async fn from_vec(path: Vec<&str>) -> Self {
         let mut path_buf = PathBuf::new();
-        path.into_iter().for_each(|it| {
-            path_buf.push(&FileInfo::parse_os_prefix(it));
+        path.into_iter().enumerate().for_each(|(index, it)| {
+            // 首段绝对路径保持不变
+            if index == 0 && Path::new(it).is_absolute() {
+                path_buf.push(it);
+            } else {
+                path_buf.push(FileInfo::parse_os_prefix(it));
+            }
         });
         if path_buf.is_relative() {
             let path = path_buf.as_os_str().to_owned();
@@ -210,99 +1227,469 @@ impl FileInfo {
     }
 
     /// 创建目录
-    pub async fn create_directory(&self) {
+    pub async fn create_directory(&self) -> std::io::Result<()> {
         let path = self.path_buf.parent().unwrap();
-        tokio::fs::create_dir_all(path).await;
+        tokio::fs::create_dir_all(path).await
     }
 
     /// 读取文件到字符串.
-    pub async fn read_string(&self) -> Result<String, FromUtf8Error> {
-        let mut file = File::open(&self.path_buf).await.unwrap();
+    pub async fn read_string(&self) -> Result<String, Error> {
+        let mut file = File::open(&self.path_buf).await?;
         let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer).await;
-        String::from_utf8(buffer)
-    }
-}
-
-
-// pub async fn read_file(&self, url: &str) -> bool {
-//     // let path = format!("/Users/xxscloud/code/test/rust/bbox/src/resource.rs");
-//     // let mut async_file = tokio::fs::File::open("/Users/xxscloud/code/github/BlueberryBox/bbox/output.log").await.unwrap();
-//
-//     // let mut file = std::fs::File::open("/Users/xxscloud/code/github/BlueberryBox/bbox/output.log").unwrap();
-//     // let mut buffer = [0u8; 16];
-//     // let mut buffer2 = [0u8; 16];
-//     // let len = async_file.read(&mut buffer).await.unwrap();
-//     // let len = async_file.read(&mut buffer2).await.unwrap();
-//     // println!("{}", len);
-//     false
-// }
-
-
-// // 首页内容
-//         let index_file_info = FileInfo::from_vec(vec![&self.local_path, "index.html"]).await;
-//         if index_file_info.exist() {
-//             *self.index_data.write().await = index_file_info.read_string().await.unwrap();
-//         } else {
-//             *self.index_data.write().await = String::new();
-//         }
-//
-//         // 静态资源文件索引
-//         let path_list = &self.scan_directory(&self.local_path).await;
-//         if path_list.len() > 0 {
-//             let mut uri_list = vec![];
-//             for it in path_list {
-//                 match it.to_str() {
-//                     None => continue,
-//                     Some(value) => uri_list.push(value[self.local_path.len() + 1..].to_owned())
-//                 }
-//             }
-//             *self.file_list.write().await = uri_list;
-//         }
-
-
-//
-// /// 扫描目录.
-// #[async_recursion]
-// async fn scan_directory(&self, local_path: &str) -> Vec<PathBuf> {
-//     let mut paths = tokio::fs::read_dir(local_path).await.unwrap();
-//     let mut path_list = vec![];
-//     loop {
-//         let item = paths.next_entry().await.unwrap();
-//         match item {
-//             None => break,
-//             Some(value) => {
-//                 let path_buf = value.path();
-//                 let path = path_buf.as_path();
-//                 if path.is_dir() {
-//                     let directory_path = path_buf.to_str().unwrap();
-//                     path_list.append(&mut self.scan_directory(directory_path).await)
-//                 }
-//                 if path.is_file() {
-//                     path_list.push(path_buf)
-//                 }
-//             }
-//         }
-//     }
-//     path_list
-// }
-//
-// /// 更新文件.
-// fn update_file(&self, event: notify::op::Op, path: PathBuf) {
-//     let uri = match path.as_os_str().to_str() {
-//         None => return,
-//         Some(value) => value[self.local_path.len() + 1..].to_owned()
-//     };
-//     if event.contains(notify::op::CREATE) {
-//         tokio::spawn(async move {
-//             println!("{:?}", FILE_LIST.read().await);
-//             FILE_LIST.write().await.push(uri);
-//         });
-//     }
-//     if event.contains(notify::op::REMOVE) {
-//         //(*self.file_list.write().await).retain(|&it| it != uri);
-//     }
-//     if event.contains(notify::op::RENAME) {
-//         // TODO
-//     }
-// }
\ No newline at end of file
+        file.read_to_end(&mut buffer).await?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{load, load_seo, remote_server, temp_dir, GLOBAL_LOCK};
+
+    fn find_title(config_list: &[Config], uri: &str) -> Option<String> {
+        Config::find_all(config_list, uri).last().and_then(|it| it.title.clone())
+    }
+
+    #[test]
+    fn match_exact() {
+        let config_list = load_seo(r#"[{"path": "exact://login", "title": "login"}]"#);
+        assert_eq!(find_title(&config_list, "/login"), Some("login".to_string()));
+        assert_eq!(find_title(&config_list, "/login/next"), None);
+        assert_eq!(find_title(&config_list, "/"), None);
+    }
+
+    #[test]
+    fn match_pre() {
+        let config_list = load_seo(r#"[{"path": "pre://index", "title": "index"}]"#);
+        assert_eq!(find_title(&config_list, "/index"), Some("index".to_string()));
+        assert_eq!(find_title(&config_list, "/index/home"), Some("index".to_string()));
+        assert_eq!(find_title(&config_list, "/ind"), None);
+        assert_eq!(find_title(&config_list, "/"), None);
+    }
+
+    #[test]
+    fn match_tail() {
+        let config_list = load_seo(r#"[{"path": "tail://detail", "title": "detail"}]"#);
+        assert_eq!(find_title(&config_list, "/goods/detail"), Some("detail".to_string()));
+        assert_eq!(find_title(&config_list, "/detail"), Some("detail".to_string()));
+        assert_eq!(find_title(&config_list, "/detail/goods"), None);
+    }
+
+    #[test]
+    fn match_regular() {
+        let config_list = load_seo(r#"[{"path": "regular://^/item/\\d+$", "title": "item"}]"#);
+        assert_eq!(find_title(&config_list, "/item/1024"), Some("item".to_string()));
+        assert_eq!(find_title(&config_list, "/item/abc"), None);
+    }
+
+    #[test]
+    fn match_catch_all() {
+        let config_list = load_seo(r#"[{"title": "none"}, {"path": "", "title": "empty"}]"#);
+        assert_eq!(find_title(&config_list, "/anything"), Some("empty".to_string()));
+    }
+
+    #[test]
+    fn match_precedence() {
+        let config_list = load_seo(r#"[
+            {"title": "all"},
+            {"path": "regular://^/goods", "title": "regular"},
+            {"path": "pre://goods", "title": "pre"},
+            {"path": "pre://goods/detail", "title": "longest pre"},
+            {"path": "exact://goods/detail/1", "title": "exact"}
+        ]"#);
+        assert_eq!(find_title(&config_list, "/goods/detail/1"), Some("exact".to_string()));
+        assert_eq!(find_title(&config_list, "/goods/detail/2"), Some("longest pre".to_string()));
+        assert_eq!(find_title(&config_list, "/goods/list"), Some("pre".to_string()));
+        assert_eq!(find_title(&config_list, "/goodsx"), Some("pre".to_string()));
+        assert_eq!(find_title(&config_list, "/home"), Some("all".to_string()));
+
+        let config_list = load_seo(r#"[{"title": "all"}, {"path": "regular://^/home$", "title": "regular"}]"#);
+        assert_eq!(find_title(&config_list, "/home"), Some("regular".to_string()));
+    }
+
+    #[test]
+    fn match_last_on_tie() {
+        let config_list = load_seo(r#"[{"path": "pre://a", "title": "first"}, {"path": "pre://a", "title": "second"}]"#);
+        assert_eq!(find_title(&config_list, "/a"), Some("second".to_string()));
+    }
+
+    #[test]
+    fn merge_config() {
+        let config_list = load_seo(r#"[
+            {"path": "pre://goods", "title": "goods", "metas": [{"name": "description", "content": "goods"}], "heads": ["<script>goods</script>"]},
+            {"metas": [{"name": "viewport", "content": "width=device-width"}, {"name": "description", "content": "all"}], "title": "all", "heads": ["<script>all</script>"]},
+            {"path": "pre://home", "title": "home"}
+        ]"#);
+        let config = Config::merge(&Config::find_all(&config_list, "/goods/1"));
+        assert_eq!(config.title, Some("goods".to_string()));
+        let metas: Vec<(&str, &str)> = config.metas.as_ref().unwrap().iter()
+            .map(|it| (it.name.as_deref().unwrap(), it.content.as_deref().unwrap())).collect();
+        assert_eq!(metas, vec![("viewport", "width=device-width"), ("description", "goods")]);
+        assert_eq!(config.heads, Some(vec!["<script>all</script>".to_string(), "<script>goods</script>".to_string()]));
+
+        let config = Config::merge(&Config::find_all(&config_list, "/about"));
+        assert_eq!(config.title, Some("all".to_string()));
+    }
+
+    #[test]
+    fn render_replace_meta() {
+        let config_list = load_seo(r#"[{"title": "goods", "metas": [{"name": "description", "content": "new"}, {"name": "keywords", "content": "k"}]}]"#);
+        let html = config_list[0].render(r#"<html><head><meta name="Description" content="old"></head><body></body></html>"#).unwrap();
+        assert_eq!(html.matches("<title>goods</title>").count(), 1);
+        assert_eq!(html.matches("description").count() + html.matches("Description").count(), 1);
+        assert!(html.contains(r#"content="new""#));
+        assert!(!html.contains(r#"content="old""#));
+        assert!(html.contains(r#"<meta name="keywords" content="k">"#));
+
+        let html = config_list[0].render("<html><head><title>old</title></head><body></body></html>").unwrap();
+        assert_eq!(html.matches("<title>").count(), 1);
+        assert!(html.contains("<title>goods</title>"));
+    }
+
+    #[test]
+    fn render_document() {
+        let config_list = load_seo(r#"[{"title": "goods"}]"#);
+        let html = config_list[0].render("<!DOCTYPE html><html lang=\"en\">\n<head>\n<title>old</title>\n</head>\n<body><div id=\"app\"></div></body>\n</html>\n").unwrap();
+        assert!(html.starts_with("<!DOCTYPE html><html lang=\"en\">"), "{}", html);
+        assert!(html.contains("<title>goods</title>"));
+        assert!(html.contains("<body><div id=\"app\"></div></body>\n</html>"));
+    }
+
+    #[test]
+    fn render_escape() {
+        let config_list = load_seo(r#"[{"title": "a<b>&\"c", "metas": [{"name": "x\"y", "content": "say \"hi\" <b>"}, {"name": "description", "content": "it's \"new\""}], "heads": ["<script>raw</script>"]}]"#);
+        let html = config_list[0].render(r#"<html><head><title></title><meta name="description" content="old"></head><body></body></html>"#).unwrap();
+        assert!(html.contains("<title>a&lt;b&gt;&amp;\"c</title>"));
+        assert!(html.contains(r#"<meta name="x&quot;y" content="say &quot;hi&quot; &lt;b&gt;">"#));
+        assert!(html.contains(r#"content="it&#39;s &quot;new&quot;""#));
+        assert!(html.contains("<script>raw</script>"));
+    }
+
+    #[tokio::test]
+    async fn render_error() {
+        let _lock = GLOBAL_LOCK.lock().await;
+        let config_list = load_seo(r#"[{"title": "a", "heads": ["<script src=\"/a.js\">"]}]"#);
+        let template = "<html><head><title>old</title></head><body></body></html>";
+        assert!(matches!(config_list[0].render(template), Err(Error::Html(_))));
+        let config_list = load_seo(r#"[{"title": "a"}]"#);
+        assert!(matches!(config_list[0].render("<html><head><div"), Err(Error::Html(_))));
+        assert!(matches!(config_list[0].render("<html><head><!--"), Err(Error::Html(_))));
+
+        // 渲染失败时使用原模板
+        let resource = Resource::from(".", "", 0, SymlinkPolicy::Root, false).await;
+        *CONFIG_DATA.write().await = load_seo(r#"[{"title": "a", "heads": ["<script src=\"/a.js\">"]}]"#);
+        *INDEX_DATA.write().await = template.to_string();
+        assert_eq!(resource.parse_html_by_config("/", None).await, template);
+        CONFIG_DATA.write().await.clear();
+        INDEX_DATA.write().await.clear();
+    }
+
+    #[test]
+    fn render_meta_variants_and_links() {
+        let config_list = load_seo(r#"[
+            {"metas": [{"property": "og:title", "content": "all"}, {"charset": "utf-8"}], "links": [{"rel": "canonical", "href": "https://a.com/"}]},
+            {"path": "pre://goods", "metas": [{"property": "og:title", "content": "goods"}, {"http-equiv": "X-UA-Compatible", "content": "IE=edge"}, {"itemprop": "name", "content": "n"}],
+             "links": [{"rel": "canonical", "href": "https://a.com/goods"}, {"rel": "alternate", "href": "https://a.com/en/goods", "hreflang": "en"}]}
+        ]"#);
+        let config = Config::merge(&Config::find_all(&config_list, "/goods"));
+        assert_eq!(config.metas.as_ref().unwrap().len(), 4);
+        assert_eq!(config.links.as_ref().unwrap().len(), 2);
+        let html = config.render(r#"<html><head><meta charset="gbk"><meta property="og:title" content="old"><link rel="canonical" href="https://old.com/"></head><body></body></html>"#).unwrap();
+        assert!(html.contains(r#"<meta charset="utf-8">"#));
+        assert!(!html.contains("gbk"));
+        assert!(html.contains(r#"<meta property="og:title" content="goods">"#));
+        assert!(html.contains(r#"<meta http-equiv="X-UA-Compatible" content="IE=edge">"#));
+        assert!(html.contains(r#"<meta itemprop="name" content="n">"#));
+        assert_eq!(html.matches("canonical").count(), 1);
+        assert!(html.contains(r#"href="https://a.com/goods""#));
+        assert!(html.contains(r#"<link rel="alternate" href="https://a.com/en/goods" hreflang="en">"#));
+    }
+
+    #[tokio::test]
+    async fn build_with_params() {
+        let config_list = load_seo(r#"[
+            {"path": "exact://product/:id", "title": "product {id}", "metas": [{"name": "description", "content": "{id} from {query.from}"}],
+             "links": [{"rel": "canonical", "href": "https://a.com/product/{id}"}], "heads": ["<script>var id = '{id}', x = {unknown};</script>"]},
+            {"path": "regular://^/shop/(?P<shop>\\w+)/", "title": "shop {shop}"}
+        ]"#);
+        let config = Config::build(&config_list, "/product/10%3C", Some("from=a+b%26c&x")).await.unwrap();
+        assert_eq!(config.title, Some("product 10<".to_string()));
+        assert_eq!(config.metas.as_ref().unwrap()[0].content, Some("10< from a b&c".to_string()));
+        assert_eq!(config.links.as_ref().unwrap()[0].href, "https://a.com/product/10<");
+        assert_eq!(config.heads, Some(vec!["<script>var id = '10&lt;', x = {unknown};</script>".to_string()]));
+        assert!(Config::build(&config_list, "/product/10/more", None).await.is_none());
+
+        let config = Config::build(&config_list, "/shop/apple/1", None).await.unwrap();
+        assert_eq!(config.title, Some("shop apple".to_string()));
+    }
+
+    #[test]
+    fn remote_valid() {
+        let config_list = load_seo(r#"[
+            {"title": "local"},
+            {"remote": "http://api.local/seo?path={path}&id={query.id}"},
+            {"remote": "https://api.local/seo"},
+            {"remote": "api.local/seo"},
+            {"remote": "http://{path}"}
+        ]"#);
+        let valid: Vec<bool> = config_list.iter().map(Config::is_valid).collect();
+        assert_eq!(valid, vec![true, true, false, false, true]);
+    }
+
+    #[test]
+    fn compile_error() {
+        let mut config: Config = load(r#"{"path": "pre://product/:a-b"}"#);
+        assert!(config.compile().is_err());
+    }
+
+    #[tokio::test]
+    async fn build_with_remote() {
+        let address = remote_server().await;
+        let config_list = load_seo(&format!(r#"[
+            {{"title": "all", "metas": [{{"name": "keywords", "content": "k"}}]}},
+            {{"path": "pre://goods", "title": "goods", "metas": [{{"name": "description", "content": "static"}}],
+              "remote": "http://{0}/seo?path={{path}}&id={{query.id}}"}},
+            {{"path": "pre://error", "title": "error", "remote": "http://{0}/error"}},
+            {{"path": "pre://head", "title": "static head", "remote": "http://{0}/head"}},
+            {{"path": "pre://slow", "title": "slow static", "remote": "http://{0}/slow", "remote_timeout": 200}}
+        ]"#, address));
+
+        let config = Config::build(&config_list, "/goods/a b", Some("id=1%262")).await.unwrap();
+        assert_eq!(config.title, Some("remote path=/goods/a%20b&id=1%262".to_string()));
+        let metas: Vec<(&str, &str)> = config.metas.as_ref().unwrap().iter()
+            .map(|it| (it.name.as_deref().unwrap(), it.content.as_deref().unwrap())).collect();
+        assert_eq!(metas, vec![("keywords", "k"), ("description", "remote")]);
+
+        let config = Config::build(&config_list, "/error", None).await.unwrap();
+        assert_eq!(config.title, Some("error".to_string()));
+
+        let config = Config::build(&config_list, "/head", None).await.unwrap();
+        assert_eq!(config.title, Some("static head".to_string()));
+        assert_eq!(config.heads, None);
+
+        let config = Config::build(&config_list, "/slow", None).await.unwrap();
+        assert_eq!(config.title, Some("slow static".to_string()));
+    }
+
+    #[tokio::test]
+    async fn page_cache() {
+        let _lock = GLOBAL_LOCK.lock().await;
+        let root = temp_dir("page-cache");
+        let config_path = root.join("config.json");
+        let config_path = config_path.to_str().unwrap();
+        std::fs::write(root.join("index.html"), "<html><head></head><body>v1</body></html>").unwrap();
+        std::fs::write(config_path, r#"[{"path": "pre://goods", "title": "goods"}, {"path": "pre://search", "title": "{query.q}"}]"#).unwrap();
+        let resource = Resource::from(root.to_str().unwrap(), config_path, 10, SymlinkPolicy::Root, false).await;
+        resource.init_config().await.unwrap();
+        resource.init_index().await;
+        let cache_len = || resource.page_cache.lock().unwrap().len();
+
+        // 命中缓存时不重新渲染
+        let html = resource.parse_html_by_config("/goods", Some("a=1")).await;
+        assert!(html.contains("<title>goods</title>") && html.contains("v1"));
+        *resource.index_data.write().await = "<html><head></head><body>changed</body></html>".to_string();
+        assert_eq!(resource.parse_html_by_config("/goods", Some("a=2")).await, html);
+        assert_eq!(cache_len(), 1);
+
+        // 引用查询参数时缓存键包含查询参数
+        assert!(resource.parse_html_by_config("/search", Some("q=a")).await.contains("<title>a</title>"));
+        assert!(resource.parse_html_by_config("/search", Some("q=b")).await.contains("<title>b</title>"));
+        assert_eq!(cache_len(), 3);
+
+        // 重新加载首页模板和配置后缓存失效
+        std::fs::write(root.join("index.html"), "<html><head></head><body>v2</body></html>").unwrap();
+        resource.init_index().await;
+        assert_eq!(cache_len(), 0);
+        assert!(resource.parse_html_by_config("/goods", None).await.contains("v2"));
+        std::fs::write(config_path, r#"[{"path": "pre://goods", "title": "new goods"}]"#).unwrap();
+        resource.init_config().await.unwrap();
+        assert_eq!(cache_len(), 0);
+        assert!(resource.parse_html_by_config("/goods", None).await.contains("<title>new goods</title>"));
+
+        // 远程 SEO 数据不缓存
+        let address = remote_server().await;
+        std::fs::write(config_path, format!(r#"[{{"path": "pre://goods", "title": "goods", "remote": "http://{}/seo"}}]"#, address)).unwrap();
+        resource.init_config().await.unwrap();
+        assert!(resource.parse_html_by_config("/goods", None).await.contains("<title>remote </title>"));
+        assert_eq!(cache_len(), 0);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn hashed_file() {
+        assert!(is_hashed_file("/js/app.3f9a1c.js"));
+        assert!(is_hashed_file("/assets/index-BsQx1aZ8.js"));
+        assert!(is_hashed_file("/assets/chunk-vendors.2b6f1c9e.css"));
+        assert!(is_hashed_file("/assets/index-BsQxaZTa.js"));
+        assert!(is_hashed_file("/assets/index-Bs-Qx1aZ.js"));
+        assert!(is_hashed_file("/assets/vendor-react-x_9Qk2Lm.js"));
+        assert!(!is_hashed_file("/index.html"));
+        assert!(!is_hashed_file("/js/chunk-vendors.js"));
+        assert!(!is_hashed_file("/js/app.decade.js"));
+        assert!(!is_hashed_file("/img/logo-20231010"));
+        assert!(!is_hashed_file("/js/page-Settings.js"));
+        assert!(!is_hashed_file("/img/icon.Facebook.svg"));
+        assert!(!is_hashed_file("/js/AccountSettings.UserProfile.js"));
+        assert!(!is_hashed_file("/js/settings-panel-component.js"));
+        assert!(!is_hashed_file("/js/jquery-3.6.0.min.js"));
+    }
+
+    #[test]
+    fn find_cache() {
+        let mut cache_list: Vec<ConfigCache> = load(r#"[
+            {"control": "no-store"},
+            {"path": "pre://static", "control": "max-age=60"},
+            {"path": "tail://.mp4", "control": "max-age=600"}
+        ]"#);
+        cache_list.iter_mut().for_each(|it| it.compile().unwrap());
+        let find = |uri: &str| ConfigCache::find(&cache_list, uri).map(|it| it.control.as_str());
+        assert_eq!(find("/static/a.mp4"), Some("max-age=60"));
+        assert_eq!(find("/video/a.mp4"), Some("max-age=600"));
+        assert_eq!(find("/index"), Some("no-store"));
+    }
+
+    #[test]
+    fn decode_request_path() {
+        assert_eq!(decode_path("/js/app.js").as_deref(), Some("js/app.js"));
+        assert_eq!(decode_path("/img/%E4%B8%AD.png").as_deref(), Some("img/中.png"));
+        assert_eq!(decode_path("//js/./app.js").as_deref(), Some("js/app.js"));
+        assert_eq!(decode_path("/"), None);
+        assert_eq!(decode_path("/../etc/passwd"), None);
+        assert_eq!(decode_path("/js/%2e%2e/%2e%2e/etc/passwd"), None);
+        assert_eq!(decode_path("/js%2f..%2f..%2fetc/passwd"), None);
+        assert_eq!(decode_path("/js/..%5c..%5cetc"), None);
+        assert_eq!(decode_path("/a%00.js"), None);
+        assert_eq!(decode_path("/%FF.js"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn resolve_symlink() {
+        let root = temp_dir("symlink");
+        std::fs::create_dir_all(root.join("static")).unwrap();
+        std::fs::create_dir_all(root.join("upload")).unwrap();
+        std::fs::write(root.join("static/a.txt"), "a").unwrap();
+        std::fs::write(root.join("upload/b.txt"), "b").unwrap();
+        std::os::unix::fs::symlink(root.join("static/a.txt"), root.join("static/inner.txt")).unwrap();
+        std::os::unix::fs::symlink(root.join("upload"), root.join("static/upload")).unwrap();
+        std::os::unix::fs::symlink(".", root.join("static/loop")).unwrap();
+
+        let local_path = root.join("static").to_str().unwrap().to_string();
+        let resolve = |symlink: SymlinkPolicy| {
+            let local_path = local_path.clone();
+            async move {
+                let resource = Resource::from(&local_path, "", 0, symlink, false).await;
+                [
+                    resource.resolve_path("a.txt").await.is_ok(),
+                    resource.resolve_path("inner.txt").await.is_ok(),
+                    resource.resolve_path("upload/b.txt").await.is_ok(),
+                ]
+            }
+        };
+        assert_eq!(resolve(SymlinkPolicy::Deny).await, [true, false, false]);
+        assert_eq!(resolve(SymlinkPolicy::Root).await, [true, true, false]);
+        assert_eq!(resolve(SymlinkPolicy::Follow).await, [true, true, true]);
+
+        // 扫描不进入符号链接目录, 链接目录内的文件按策略解析
+        let _lock = GLOBAL_LOCK.lock().await;
+        let resource = Resource::from(&local_path, "", 0, SymlinkPolicy::Root, false).await;
+        resource.init_files().await;
+        let mut uri_list: Vec<String> = resource.file_list.read().await.iter().cloned().collect();
+        uri_list.sort();
+        assert_eq!(uri_list, vec!["a.txt", "inner.txt", "loop", "upload"]);
+        let is_static_file = |url: &'static str| {
+            let resource = resource.clone();
+            async move { resource.is_static_file(url, None).await.map(|it| it.map(|it| it.path)) }
+        };
+        assert_eq!(is_static_file("/loop/loop/a.txt").await.unwrap(), Some(root.join("static/a.txt").canonicalize().unwrap()));
+        assert_eq!(is_static_file("/loop").await.unwrap(), None);
+        assert_eq!(is_static_file("/loop/missing.txt").await.unwrap(), None);
+        assert_eq!(is_static_file("/a.txt/b.txt").await.unwrap(), None);
+        assert!(matches!(is_static_file("/upload").await, Err(Error::Forbidden(_))));
+        assert!(matches!(is_static_file("/upload/b.txt").await, Err(Error::Forbidden(_))));
+        let resource = Resource::from(&local_path, "", 0, SymlinkPolicy::Follow, false).await;
+        assert!(resource.is_static_file("/upload/b.txt", None).await.unwrap().is_some());
+        assert!(resource.is_static_file("/upload", None).await.unwrap().is_none());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn update_file() {
+        let _lock = GLOBAL_LOCK.lock().await;
+        let root = temp_dir("update");
+        std::fs::write(root.join("index.html"), "<html><head></head><body>v1</body></html>").unwrap();
+        let resource = Resource::from(root.to_str().unwrap(), "", 0, SymlinkPolicy::Root, false).await;
+        resource.init_files().await;
+        resource.init_index().await;
+        let root = std::fs::canonicalize(&root).unwrap();
+        let file_list = || async { resource.file_list.read().await.iter().cloned().collect::<std::collections::BTreeSet<_>>() };
+
+        // 文件
+        std::fs::write(root.join("a.js"), "a").unwrap();
+        resource.update_file(&root, Op::CREATE, &root.join("a.js")).await;
+        std::fs::rename(root.join("a.js"), root.join("b.js")).unwrap();
+        resource.update_file(&root, Op::RENAME, &root.join("a.js")).await;
+        resource.update_file(&root, Op::RENAME, &root.join("b.js")).await;
+        assert_eq!(file_list().await, ["b.js", "index.html"].iter().map(|it| it.to_string()).collect());
+        std::fs::remove_file(root.join("b.js")).unwrap();
+        resource.update_file(&root, Op::REMOVE, &root.join("b.js")).await;
+        assert_eq!(file_list().await, ["index.html"].iter().map(|it| it.to_string()).collect());
+
+        // 目录
+        std::fs::create_dir_all(root.join("assets/js")).unwrap();
+        std::fs::write(root.join("assets/js/app.js"), "app").unwrap();
+        resource.update_file(&root, Op::CREATE, &root.join("assets")).await;
+        std::fs::rename(root.join("assets"), root.join("static")).unwrap();
+        resource.update_file(&root, Op::RENAME, &root.join("assets")).await;
+        resource.update_file(&root, Op::RENAME, &root.join("static")).await;
+        assert_eq!(file_list().await, ["index.html", "static/js/app.js"].iter().map(|it| it.to_string()).collect());
+        std::fs::remove_dir_all(root.join("static")).unwrap();
+        resource.update_file(&root, Op::REMOVE, &root.join("static")).await;
+        assert_eq!(file_list().await, ["index.html"].iter().map(|it| it.to_string()).collect());
+
+        // 首页模板, 写入一半的文件不替换原模板
+        std::fs::write(root.join("index.html"), "<html><head><div").unwrap();
+        resource.update_file(&root, Op::CREATE, &root.join("index.html")).await;
+        assert!(resource.index_data.read().await.contains("v1"));
+        std::fs::write(root.join("index.html"), "<html><head></head><body>v2</body></html>").unwrap();
+        resource.update_file(&root, Op::RENAME, &root.join("index.html")).await;
+        assert!(resource.index_data.read().await.contains("v2"));
+        std::fs::remove_file(root.join("index.html")).unwrap();
+        resource.update_file(&root, Op::REMOVE, &root.join("index.html")).await;
+        assert!(resource.index_data.read().await.is_empty());
+        assert!(file_list().await.is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn config_error() {
+        let _lock = GLOBAL_LOCK.lock().await;
+        let root = temp_dir("config");
+        let config_path = root.join("config.json");
+        let config_path = config_path.to_str().unwrap();
+        let resource = Resource::from(root.to_str().unwrap(), config_path, 0, SymlinkPolicy::Root, false).await;
+
+        std::fs::write(config_path, "{\n  \"seo\": [\n    {\"title\": \"a\",}\n  ]\n}").unwrap();
+        let message = resource.init_config().await.unwrap_err().to_string();
+        assert!(message.contains(config_path), "{}", message);
+        assert!(message.contains("line 3 column"), "{}", message);
+
+        std::fs::write(config_path, r#"{"cache": [{"path": "regular://(", "control": "no-cache"}]}"#).unwrap();
+        let message = resource.init_config().await.unwrap_err().to_string();
+        assert!(message.contains("cache entry #0 path 'regular://('"), "{}", message);
+
+        std::fs::write(config_path, r#"{"seo": [{"title": "a"}, {"remote": "https://api.local/seo"}]}"#).unwrap();
+        let message = resource.init_config().await.unwrap_err().to_string();
+        assert!(message.contains("seo entry #1 remote 'https://api.local/seo'"), "{}", message);
+
+        std::fs::write(config_path, r#"{"seo": [{"title": "a"}, {"heads": ["<meta name=\"a\">", "<script src=\"/a.js\">"]}]}"#).unwrap();
+        let message = resource.init_config().await.unwrap_err().to_string();
+        assert!(message.contains("seo entry #1 head '<script src=\"/a.js\">'"), "{}", message);
+
+        std::fs::write(config_path, [0xffu8, 0xfe]).unwrap();
+        assert!(matches!(resource.init_config().await, Err(Error::Config { .. })));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt;
+use std::io;
+use std::string::FromUtf8Error;
+use hyper::StatusCode;
+use hyper::header::InvalidHeaderValue;
+use hyper::http::uri::InvalidUri;
+
+/// 核心和服务错误.
+#[derive(Debug)]
+pub enum Error {
+    /// 文件读写错误.
+    Io(io::Error),
+    /// 文件不是 UTF-8 编码.
+    Utf8(FromUtf8Error),
+    /// JSON 格式错误.
+    Json(serde_json::Error),
+    /// 配置路径正则错误.
+    Regular {
+        /// 配置项, seo 或 cache.
+        section: &'static str,
+        /// 配置序号.
+        index: usize,
+        /// 配置路径.
+        path: String,
+        /// 正则错误.
+        source: regex::Error,
+    },
+    /// 自定义内容类型不是合法的响应头.
+    Mime {
+        /// 扩展名.
+        extension: String,
+        /// 内容类型.
+        value: String,
+    },
+    /// 远程 SEO 数据地址错误.
+    Remote {
+        /// 配置序号.
+        index: usize,
+        /// 远程 SEO 数据地址.
+        remote: String,
+    },
+    /// 自定义头部不是完整的 HTML.
+    Head {
+        /// 配置序号.
+        index: usize,
+        /// 自定义头部.
+        head: String,
+        /// 解析错误.
+        source: Box<Error>,
+    },
+    /// 反向代理上游服务地址错误.
+    Upstream {
+        /// 配置序号.
+        index: usize,
+        /// 上游服务地址.
+        upstream: String,
+    },
+    /// 配置文件错误.
+    Config {
+        /// 配置文件路径.
+        path: String,
+        /// 原始错误.
+        source: Box<Error>,
+    },
+    /// TLS 证书或私钥错误.
+    Tls {
+        /// 证书或私钥路径.
+        path: String,
+        /// 错误信息.
+        message: String,
+    },
+    /// HTML 解析错误.
+    Html(String),
+    /// 请求路径包含 . 或 .. 等路径段.
+    BadPath(String),
+    /// 请求路径越界或不符合符号链接策略.
+    Forbidden(String),
+    /// 响应头错误.
+    Header(InvalidHeaderValue),
+    /// 请求地址错误.
+    Uri(InvalidUri),
+    /// 上游服务请求失败.
+    Gateway(hyper::Error),
+    /// 上游服务响应超时.
+    Timeout(String),
+}
+
+impl Error {
+    /// 错误对应的响应状态码.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Error::Io(e) if e.kind() == io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            Error::Io(e) if e.kind() == io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+            Error::BadPath(_) => StatusCode::BAD_REQUEST,
+            Error::Forbidden(_) => StatusCode::FORBIDDEN,
+            Error::Gateway(_) => StatusCode::BAD_GATEWAY,
+            Error::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Utf8(e) => write!(f, "{}", e),
+            Error::Json(e) => write!(f, "parse error: {}", e),
+            Error::Regular { section, index, path, source } =>
+                write!(f, "{} entry #{} path '{}' regular error: {}", section, index, path, source),
+            Error::Mime { extension, value } =>
+                write!(f, "mime '{}' value '{}' is not a valid header value", extension, value),
+            Error::Remote { index, remote } =>
+                write!(f, "seo entry #{} remote '{}' is not a valid http url", index, remote),
+            Error::Head { index, head, source } =>
+                write!(f, "seo entry #{} head '{}' {}", index, head, source),
+            Error::Upstream { index, upstream } =>
+                write!(f, "proxy entry #{} upstream '{}' is not a valid http url", index, upstream),
+            Error::Config { path, source } => write!(f, "Config '{}' {}", path, source),
+            Error::Tls { path, message } => write!(f, "TLS '{}' error: {}", path, message),
+            Error::Html(e) => write!(f, "html parse error: {}", e),
+            Error::BadPath(path) => write!(f, "Bad path '{}'", path),
+            Error::Forbidden(path) => write!(f, "Forbidden path '{}'", path),
+            Error::Header(e) => write!(f, "{}", e),
+            Error::Uri(e) => write!(f, "{}", e),
+            Error::Gateway(e) => write!(f, "upstream error: {}", e),
+            Error::Timeout(upstream) => write!(f, "upstream '{}' timeout", upstream),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<FromUtf8Error> for Error {
+    fn from(e: FromUtf8Error) -> Self {
+        Error::Utf8(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<InvalidUri> for Error {
+    fn from(e: InvalidUri) -> Self {
+        Error::Uri(e)
+    }
+}
+
+impl From<InvalidHeaderValue> for Error {
+    fn from(e: InvalidHeaderValue) -> Self {
+        Error::Header(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_status() {
+        assert_eq!(Error::Io(io::Error::from(io::ErrorKind::NotFound)).status(), StatusCode::NOT_FOUND);
+        assert_eq!(Error::Io(io::Error::from(io::ErrorKind::PermissionDenied)).status(), StatusCode::FORBIDDEN);
+        assert_eq!(Error::Io(io::Error::from(io::ErrorKind::Other)).status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(Error::BadPath("/api/../a".to_string()).status(), StatusCode::BAD_REQUEST);
+        assert_eq!(Error::Forbidden("/../a".to_string()).status(), StatusCode::FORBIDDEN);
+        assert_eq!(Error::Timeout("http://127.0.0.1".to_string()).status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+}
@@ -0,0 +1,93 @@
+use serde::Serialize;
+use crate::core::{encode_url, escape_html};
+
+/// 目录列表项.
+#[derive(Serialize, Debug)]
+pub struct ListingEntry {
+    /// 文件名.
+    pub name: String,
+    /// 是否是目录.
+    pub directory: bool,
+    /// 文件大小, 目录为 0.
+    pub size: u64,
+    /// 修改时间.
+    pub modified: Option<String>,
+}
+
+/// 是否请求 JSON 格式目录列表, 支持 ?format=json 和 Accept: application/json.
+pub fn is_json(accept: Option<&str>, query: Option<&str>) -> bool {
+    if query.unwrap_or_default().split('&').any(|it| it == "format=json") {
+        return true;
+    }
+    accept.is_some_and(|it| it.contains("application/json") && !it.contains("text/html"))
+}
+
+/// 目录显示路径.
+fn display_path(uri: &str) -> String {
+    match uri.is_empty() {
+        true => "/".to_string(),
+        false => format!("/{}/", uri)
+    }
+}
+
+/// 输出 HTML 目录列表.
+pub fn to_html(uri: &str, entry_list: &[ListingEntry]) -> String {
+    let title = escape_html(&format!("Index of {}", display_path(uri)));
+    let mut html = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n\
+        <body>\n<h1>{0}</h1>\n<table>\n<tr><th>Name</th><th>Size</th><th>Modified</th></tr>\n", title);
+    if !uri.is_empty() {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for it in entry_list {
+        let suffix = if it.directory { "/" } else { "" };
+        let size = if it.directory { "-".to_string() } else { it.size.to_string() };
+        html.push_str(&format!("<tr><td><a href=\"{}{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>\n",
+                               escape_html(&encode_url(&it.name)), suffix, escape_html(&it.name), suffix,
+                               size, it.modified.as_deref().unwrap_or_default()));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+/// 输出 JSON 目录列表.
+pub fn to_json(uri: &str, entry_list: &[ListingEntry]) -> String {
+    serde_json::json!({
+        "path": display_path(uri),
+        "entries": entry_list,
+    }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_list() -> Vec<ListingEntry> {
+        vec![
+            ListingEntry { name: "docs".to_string(), directory: true, size: 0, modified: None },
+            ListingEntry { name: "a <b>.txt".to_string(), directory: false, size: 12, modified: None },
+        ]
+    }
+
+    #[test]
+    fn json_format() {
+        assert!(is_json(None, Some("format=json")));
+        assert!(is_json(Some("application/json"), None));
+        assert!(!is_json(Some("text/html,application/xhtml+xml,application/json;q=0.9"), None));
+        assert!(!is_json(None, None));
+    }
+
+    #[test]
+    fn render_listing() {
+        let html = to_html("files", &entry_list());
+        assert!(html.contains("<title>Index of /files/</title>"));
+        assert!(html.contains("<a href=\"../\">"));
+        assert!(html.contains("<a href=\"docs/\">docs/</a>"));
+        assert!(html.contains("<a href=\"a%20%3Cb%3E.txt\">a &lt;b&gt;.txt</a></td><td>12</td>"));
+        assert!(!to_html("", &[]).contains("../"));
+
+        let json: serde_json::Value = serde_json::from_str(&to_json("", &entry_list())).unwrap();
+        assert_eq!(json["path"], "/");
+        assert_eq!(json["entries"][0]["name"], "docs");
+        assert_eq!(json["entries"][1]["size"], 12);
+    }
+}
@@ -5,6 +5,17 @@ mod server;
 mod core;
 mod setting;
 mod args;
+mod range;
+mod condition;
+mod compress;
+mod mime;
+mod listing;
+mod error;
+mod proxy;
+mod tls;
+mod watch;
+#[cfg(test)]
+mod test_util;
 
 
 pub fn print_banner() {
@@ -27,7 +38,17 @@ ______  _               _                              ______
 async fn main() {
     let args: Args = Args::parse();
     print_banner();
-    server::start(args).await.unwrap();
+    if args.template {
+        if let Err(e) = setting::output_config_json().await {
+            eprintln!("BlueberryBox template error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Err(e) = server::start(args).await {
+        eprintln!("BlueberryBox start error: {}", e);
+        std::process::exit(1);
+    }
 }
 
 
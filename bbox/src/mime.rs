@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+/// 未知扩展名的内容类型.
+pub const DEFAULT_TYPE: &str = "application/octet-stream";
+
+/// 扩展名对应的内容类型.
+const MIME_LIST: [(&str, &str); 73] = [
+    // 文本
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("cjs", "text/javascript"),
+    ("txt", "text/plain"),
+    ("csv", "text/csv"),
+    ("md", "text/markdown"),
+    ("vtt", "text/vtt"),
+    ("ics", "text/calendar"),
+    // 数据
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("jsonld", "application/ld+json"),
+    ("webmanifest", "application/manifest+json"),
+    ("xml", "application/xml"),
+    ("rss", "application/rss+xml"),
+    ("atom", "application/atom+xml"),
+    ("xhtml", "application/xhtml+xml"),
+    ("wasm", "application/wasm"),
+    // 图片
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("bmp", "image/bmp"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("apng", "image/apng"),
+    // 字体
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("eot", "application/vnd.ms-fontobject"),
+    // 音频
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("ogg", "audio/ogg"),
+    ("oga", "audio/ogg"),
+    ("opus", "audio/opus"),
+    ("m4a", "audio/mp4"),
+    ("aac", "audio/aac"),
+    ("flac", "audio/flac"),
+    ("weba", "audio/webm"),
+    ("mid", "audio/midi"),
+    ("midi", "audio/midi"),
+    // 视频
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("webm", "video/webm"),
+    ("ogv", "video/ogg"),
+    ("mov", "video/quicktime"),
+    ("avi", "video/x-msvideo"),
+    ("mpeg", "video/mpeg"),
+    ("ts", "video/mp2t"),
+    ("m3u8", "application/vnd.apple.mpegurl"),
+    // 文档
+    ("pdf", "application/pdf"),
+    ("doc", "application/msword"),
+    ("docx", "application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+    ("xls", "application/vnd.ms-excel"),
+    ("xlsx", "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    ("pptx", "application/vnd.openxmlformats-officedocument.presentationml.presentation"),
+    ("epub", "application/epub+zip"),
+    // 压缩包
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("7z", "application/x-7z-compressed"),
+    ("rar", "application/vnd.rar"),
+    ("bz2", "application/x-bzip2"),
+    ("apk", "application/vnd.android.package-archive"),
+    ("exe", "application/octet-stream"),
+];
+
+/// 根据扩展名获取内容类型, 优先使用自定义配置, 文本类型追加 UTF-8 编码.
+pub fn content_type(extension: &str, custom: &HashMap<String, String>) -> String {
+    let extension = extension.to_lowercase();
+    let value = match custom.get(&extension) {
+        Some(value) => value.as_str(),
+        None => MIME_LIST.iter().find(|(key, _)| *key == extension).map(|(_, value)| *value).unwrap_or(DEFAULT_TYPE)
+    };
+    if is_text(value) && !value.contains("charset=") {
+        format!("{}; charset=utf-8", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// 是否是文本内容类型.
+pub fn is_text(content_type: &str) -> bool {
+    let content_type = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    content_type.starts_with("text/")
+        || content_type == "application/javascript"
+        || content_type == "application/json"
+        || content_type == "application/xml"
+        || content_type.ends_with("+json")
+        || (content_type.starts_with("application/") && content_type.ends_with("+xml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_type() {
+        let custom = HashMap::new();
+        assert_eq!(content_type("txt", &custom), "text/plain; charset=utf-8");
+        assert_eq!(content_type("JS", &custom), "text/javascript; charset=utf-8");
+        assert_eq!(content_type("json", &custom), "application/json; charset=utf-8");
+        assert_eq!(content_type("webmanifest", &custom), "application/manifest+json; charset=utf-8");
+        assert_eq!(content_type("jpg", &custom), "image/jpeg");
+        assert_eq!(content_type("svg", &custom), "image/svg+xml");
+        assert_eq!(content_type("woff2", &custom), "font/woff2");
+        assert_eq!(content_type("wasm", &custom), "application/wasm");
+        assert_eq!(content_type("pdf", &custom), "application/pdf");
+        assert_eq!(content_type("xlsx", &custom), "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet");
+        assert_eq!(content_type("unknown", &custom), DEFAULT_TYPE);
+        assert_eq!(content_type("", &custom), DEFAULT_TYPE);
+    }
+
+    #[test]
+    fn custom_type() {
+        let mut custom = HashMap::new();
+        custom.insert("txt".to_string(), "text/x-log".to_string());
+        custom.insert("data".to_string(), "application/x-data".to_string());
+        custom.insert("gbk".to_string(), "text/plain; charset=gbk".to_string());
+        assert_eq!(content_type("txt", &custom), "text/x-log; charset=utf-8");
+        assert_eq!(content_type("data", &custom), "application/x-data");
+        assert_eq!(content_type("gbk", &custom), "text/plain; charset=gbk");
+    }
+}
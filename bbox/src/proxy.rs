@@ -0,0 +1,233 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+use hyper::{Body, Client, HeaderMap, Request, Response, StatusCode, Version};
+use hyper::client::HttpConnector;
+use hyper::upgrade::OnUpgrade;
+use hyper::header::{HeaderName, HeaderValue, CONNECTION, HOST, PROXY_AUTHENTICATE, PROXY_AUTHORIZATION, TE, TRAILER,
+                    TRANSFER_ENCODING, UPGRADE};
+use lazy_static::lazy_static;
+use log::{error, info};
+use crate::core::ConfigProxy;
+use crate::error::Error;
+
+lazy_static! {
+    static ref PROXY_CLIENT: Client<HttpConnector> = Client::new();
+}
+
+/// 逐跳请求头, 不转发.
+const HOP_HEADERS: [HeaderName; 7] = [CONNECTION, PROXY_AUTHENTICATE, PROXY_AUTHORIZATION, TE, TRAILER, TRANSFER_ENCODING, UPGRADE];
+
+/// 转发请求到上游服务, 流式转发请求和响应内容, WebSocket 升级后双向转发连接.
+pub async fn forward(mut request: Request<Body>, proxy: &ConfigProxy, remote: Option<SocketAddr>, proto: &'static str)
+                     -> Result<Response<Body>, Error> {
+    let websocket = is_websocket(request.headers());
+    let client_upgrade = if websocket { Some(hyper::upgrade::on(&mut request)) } else { None };
+    // HTTP/2 请求没有 Host, 使用 :authority
+    let host = request.headers().get(HOST).cloned()
+        .or_else(|| request.uri().authority().and_then(|it| HeaderValue::from_str(it.as_str()).ok()));
+    *request.uri_mut() = proxy.upstream(request.uri())?;
+    // 上游使用 HTTP/1.1 客户端, 客户端可能通过 HTTP/2 请求
+    *request.version_mut() = Version::HTTP_11;
+    let headers = request.headers_mut();
+    remove_hop_headers(headers);
+    if websocket {
+        headers.insert(CONNECTION, HeaderValue::from_static("upgrade"));
+        headers.insert(UPGRADE, HeaderValue::from_static("websocket"));
+    }
+    forwarded_headers(headers, remote, proto, host);
+    // Host 由客户端按上游服务地址设置
+    headers.remove(HOST);
+
+    let mut response = match tokio::time::timeout(Duration::from_millis(proxy.timeout), PROXY_CLIENT.request(request)).await {
+        Ok(value) => value.map_err(Error::Gateway)?,
+        Err(_) => return Err(Error::Timeout(proxy.upstream.clone()))
+    };
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        if let Some(client_upgrade) = client_upgrade {
+            let upstream_upgrade = hyper::upgrade::on(&mut response);
+            tokio::spawn(splice(client_upgrade, upstream_upgrade, proxy.upstream.clone()));
+            return Ok(response);
+        }
+    }
+    remove_hop_headers(response.headers_mut());
+    Ok(response)
+}
+
+/// 是否是 WebSocket 升级请求.
+fn is_websocket(headers: &HeaderMap<HeaderValue>) -> bool {
+    let upgrade = headers.get(UPGRADE).and_then(|it| it.to_str().ok())
+        .is_some_and(|it| it.eq_ignore_ascii_case("websocket"));
+    let connection = headers.get_all(CONNECTION).iter()
+        .filter_map(|it| it.to_str().ok())
+        .flat_map(|it| it.split(','))
+        .any(|it| it.trim().eq_ignore_ascii_case("upgrade"));
+    upgrade && connection
+}
+
+/// 等待客户端和上游服务完成升级, 双向转发连接数据.
+async fn splice(client_upgrade: OnUpgrade, upstream_upgrade: OnUpgrade, upstream: String) {
+    let (mut client, mut upstream_io) = match tokio::try_join!(client_upgrade, upstream_upgrade) {
+        Ok(value) => value,
+        Err(e) => {
+            error!("Upgrade '{}' error: {}", upstream, e);
+            return;
+        }
+    };
+    if let Err(e) = tokio::io::copy_bidirectional(&mut client, &mut upstream_io).await {
+        info!("WebSocket '{}' closed: {}", upstream, e);
+    }
+}
+
+/// 移除逐跳请求头和 Connection 中声明的请求头.
+fn remove_hop_headers(headers: &mut HeaderMap<HeaderValue>) {
+    let connection_list: Vec<String> = headers.get_all(CONNECTION).iter()
+        .filter_map(|it| it.to_str().ok())
+        .flat_map(|it| it.split(','))
+        .map(|it| it.trim().to_lowercase())
+        .filter(|it| !it.is_empty())
+        .collect();
+    for it in connection_list {
+        headers.remove(it.as_str());
+    }
+    for it in HOP_HEADERS.iter() {
+        headers.remove(it);
+    }
+    headers.remove("keep-alive");
+}
+
+/// 添加 X-Forwarded-For / X-Forwarded-Proto / X-Forwarded-Host, Unix 域套接字连接不追加客户端地址.
+fn forwarded_headers(headers: &mut HeaderMap<HeaderValue>, remote: Option<SocketAddr>, proto: &'static str,
+                     host: Option<HeaderValue>) {
+    if let Some(remote) = remote {
+        let forwarded_for = match headers.get("x-forwarded-for").and_then(|it| it.to_str().ok()) {
+            None => remote.ip().to_string(),
+            Some(value) => format!("{}, {}", value, remote.ip())
+        };
+        if let Ok(value) = HeaderValue::from_str(&forwarded_for) {
+            headers.insert("x-forwarded-for", value);
+        }
+    }
+    headers.insert("x-forwarded-proto", HeaderValue::from_static(proto));
+    if let Some(host) = host {
+        headers.insert("x-forwarded-host", host);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::Method;
+    use crate::test_util::{load, serve, upstream_server, websocket_server};
+
+    #[test]
+    fn find_proxy() {
+        let proxy_list: Vec<ConfigProxy> = load(r#"[
+            {"path": "/api", "upstream": "http://127.0.0.1:8080"},
+            {"path": "/api/v2/", "upstream": "http://127.0.0.1:8082", "strip": true}
+        ]"#);
+        let find = |uri: &str| ConfigProxy::find(&proxy_list, uri).map(|it| it.upstream.as_str());
+        assert_eq!(find("/api"), Some("http://127.0.0.1:8080"));
+        assert_eq!(find("/api/user"), Some("http://127.0.0.1:8080"));
+        assert_eq!(find("/api/v2/user"), Some("http://127.0.0.1:8082"));
+        assert_eq!(find("/apis"), None);
+        assert_eq!(find("/index"), None);
+        assert!(proxy_list.iter().all(ConfigProxy::is_valid));
+        assert!(!load::<ConfigProxy>(r#"{"path": "/api", "upstream": "https://127.0.0.1"}"#).is_valid());
+        assert!(!load::<ConfigProxy>(r#"{"path": "/api", "upstream": "127.0.0.1:8080"}"#).is_valid());
+    }
+
+    #[test]
+    fn upstream_uri() {
+        let proxy_list: Vec<ConfigProxy> = load(r#"[
+            {"path": "/api", "upstream": "http://127.0.0.1:8080/"},
+            {"path": "/api/", "upstream": "http://127.0.0.1:8080/v1", "strip": true}
+        ]"#);
+        let upstream = |index: usize, uri: &str| proxy_list[index].upstream(&uri.parse().unwrap()).unwrap().to_string();
+        assert_eq!(upstream(0, "/api/user?id=1"), "http://127.0.0.1:8080/api/user?id=1");
+        assert_eq!(upstream(1, "/api/user?id=1"), "http://127.0.0.1:8080/v1/user?id=1");
+        assert_eq!(upstream(1, "/api"), "http://127.0.0.1:8080/v1/");
+
+        // . 和 .. 路径段不转发
+        for uri in ["/api/../admin/secret", "/api/%2e%2e/admin", "/api/./user", "/api/..%5Cadmin"] {
+            assert!(matches!(proxy_list[1].upstream(&uri.parse().unwrap()), Err(Error::BadPath(_))), "{}", uri);
+        }
+    }
+
+    #[test]
+    fn hop_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONNECTION, HeaderValue::from_static("keep-alive, x-private"));
+        headers.insert("keep-alive", HeaderValue::from_static("timeout=5"));
+        headers.insert("x-private", HeaderValue::from_static("1"));
+        headers.insert(TE, HeaderValue::from_static("trailers"));
+        headers.insert("x-public", HeaderValue::from_static("1"));
+        remove_hop_headers(&mut headers);
+        assert_eq!(headers.len(), 1);
+        assert!(headers.contains_key("x-public"));
+    }
+
+    #[tokio::test]
+    async fn forward_request() {
+        let address = upstream_server().await;
+        let proxy: ConfigProxy = load(&format!(r#"{{"path": "/api", "upstream": "http://{}", "strip": true, "timeout": 500}}"#, address));
+        let remote = Some("10.0.0.2:5000".parse().unwrap());
+
+        let request = Request::builder().method(Method::POST).uri("/api/user?id=1")
+            .header(HOST, "www.example.com")
+            .header("x-forwarded-for", "10.0.0.1")
+            .body(Body::from("hello")).unwrap();
+        let response = forward(request, &proxy, remote, "http").await.unwrap();
+        let header = |name: &str| response.headers().get(name).map(|it| it.to_str().unwrap().to_string());
+        assert_eq!(header("echo-method").as_deref(), Some("POST"));
+        assert_eq!(header("echo-uri").as_deref(), Some("/user?id=1"));
+        assert_eq!(header("echo-host"), Some(address.to_string()));
+        assert_eq!(header("echo-x-forwarded-for").as_deref(), Some("10.0.0.1, 10.0.0.2"));
+        assert_eq!(header("echo-x-forwarded-proto").as_deref(), Some("http"));
+        assert_eq!(header("echo-x-forwarded-host").as_deref(), Some("www.example.com"));
+        assert_eq!(header("x-upstream"), None);
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "hello");
+
+        let request = Request::builder().uri("/api/slow").body(Body::empty()).unwrap();
+        let error = forward(request, &proxy, remote, "http").await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        let proxy: ConfigProxy = load(r#"{"path": "/api", "upstream": "http://127.0.0.1:1"}"#);
+        let request = Request::builder().uri("/api/user").body(Body::empty()).unwrap();
+        let error = forward(request, &proxy, remote, "http").await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn forward_websocket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let upstream = websocket_server().await;
+        let proxy: ConfigProxy = load(&format!(r#"{{"path": "/ws", "upstream": "http://{}"}}"#, upstream));
+        let address = serve(move |request| {
+            let proxy = proxy.clone();
+            async move { forward(request, &proxy, Some("10.0.0.2:5000".parse().unwrap()), "http").await.unwrap() }
+        }).await;
+
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"GET /ws/chat HTTP/1.1\r\nHost: www.example.com\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+            Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n").await.unwrap();
+        let mut buffer = vec![0u8; 1024];
+        let mut head = String::new();
+        while !head.ends_with("\r\n\r\n") {
+            let size = stream.read(&mut buffer).await.unwrap();
+            assert!(size > 0);
+            head.push_str(std::str::from_utf8(&buffer[..size]).unwrap());
+        }
+        let head = head.to_lowercase();
+        assert!(head.starts_with("http/1.1 101"), "{}", head);
+        assert!(head.contains("upgrade: websocket"), "{}", head);
+        assert!(head.contains("sec-websocket-accept: s3pplmbitxaq9kygzzhzrbk+xoo="), "{}", head);
+
+        // 掩码文本帧 "hello", 回显服务原样返回
+        let frame = [0x81u8, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+        stream.write_all(&frame).await.unwrap();
+        let mut echo = [0u8; 11];
+        stream.read_exact(&mut echo).await.unwrap();
+        assert_eq!(echo, frame);
+    }
+}
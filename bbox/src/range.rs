@@ -0,0 +1,95 @@
+/// 最多支持的范围数量, 超过时返回完整内容.
+const MAX_RANGES: usize = 16;
+
+/// 请求范围解析结果.
+#[derive(Debug, PartialEq)]
+pub enum ByteRange {
+    /// 返回完整内容.
+    Full,
+    /// 返回部分内容, 闭区间.
+    Partial(Vec<(u64, u64)>),
+    /// 范围无法满足.
+    Unsatisfiable,
+}
+
+/// 解析 Range 请求头, 无法识别的单位或格式按完整内容处理.
+pub fn parse_range(value: &str, length: u64) -> ByteRange {
+    let value = match value.trim().strip_prefix("bytes=") {
+        None => return ByteRange::Full,
+        Some(value) => value
+    };
+    let mut range_list = vec![];
+    for it in value.split(',').map(str::trim).filter(|it| !it.is_empty()) {
+        let (start, end) = match it.split_once('-') {
+            None => return ByteRange::Full,
+            Some(value) => value
+        };
+        let (start, end) = (start.trim(), end.trim());
+        if start.is_empty() {
+            // 后缀范围 -n
+            let suffix: u64 = match end.parse() {
+                Ok(value) => value,
+                Err(_) => return ByteRange::Full
+            };
+            if suffix > 0 && length > 0 {
+                range_list.push((length.saturating_sub(suffix), length - 1));
+            }
+            continue;
+        }
+        let start: u64 = match start.parse() {
+            Ok(value) => value,
+            Err(_) => return ByteRange::Full
+        };
+        let end: u64 = if end.is_empty() {
+            u64::MAX
+        } else {
+            match end.parse() {
+                Ok(value) => value,
+                Err(_) => return ByteRange::Full
+            }
+        };
+        if start > end {
+            return ByteRange::Full;
+        }
+        if start < length {
+            range_list.push((start, end.min(length - 1)));
+        }
+    }
+    if range_list.len() > MAX_RANGES {
+        return ByteRange::Full;
+    }
+    if range_list.is_empty() {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(range_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_single() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(vec![(0, 99)]));
+        assert_eq!(parse_range("bytes=900-", 1000), ByteRange::Partial(vec![(900, 999)]));
+        assert_eq!(parse_range("bytes=900-2000", 1000), ByteRange::Partial(vec![(900, 999)]));
+        assert_eq!(parse_range("bytes=-100", 1000), ByteRange::Partial(vec![(900, 999)]));
+        assert_eq!(parse_range("bytes=-2000", 1000), ByteRange::Partial(vec![(0, 999)]));
+    }
+
+    #[test]
+    fn parse_multi() {
+        assert_eq!(parse_range("bytes=0-0, -1", 1000), ByteRange::Partial(vec![(0, 0), (999, 999)]));
+        assert_eq!(parse_range("bytes=0-1,2000-3000", 1000), ByteRange::Partial(vec![(0, 1)]));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=5-1", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=a-b", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), ByteRange::Unsatisfiable);
+    }
+}
@@ -1,22 +1,49 @@
 use std::convert::Infallible;
-use std::ffi::OsStr;
-use std::net::SocketAddr;
-use hyper::{Body, HeaderMap, Method, Request, Response, Server, StatusCode};
-use hyper::header::HeaderValue;
-use hyper::service::{make_service_fn, service_fn};
+use std::io::{Cursor, SeekFrom};
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use hyper::{Body, HeaderMap, Method, Request, Response, StatusCode};
+use hyper::body::Bytes;
+use hyper::header::{HeaderValue, ACCEPT, ACCEPT_ENCODING, ACCEPT_RANGES, ALLOW, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE,
+                    CONTENT_TYPE, ETAG, HOST, IF_RANGE, LAST_MODIFIED, LOCATION, RANGE, VARY, X_CONTENT_TYPE_OPTIONS};
+use hyper::http::uri::Authority;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
 use lazy_static::lazy_static;
-use log::{error, info};
+use log::{error, info, warn};
+use socket2::{Domain, Protocol, Socket, Type};
 use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, BufReader};
 use tokio::sync::RwLock;
-use crate::{Args, core, setting};
-use crate::core::Resource;
+use tokio_rustls::TlsAcceptor;
+use tokio_util::io::ReaderStream;
+use crate::args::Bind;
+use crate::{Args, compress, condition, listing, proxy, range, setting, tls};
+use crate::compress::{Encoding, COMPRESS_MIN_SIZE, ENCODING_LIST};
+use crate::core::{encode_url, Resource, StaticFile};
+use crate::error::Error;
+use crate::range::ByteRange;
+use crate::tls::CertResolver;
 
 lazy_static! {
     static ref RESOURCE: RwLock<Option<Resource>> = RwLock::new(None);
     static ref EXCLUDE: Vec<&'static str> = vec!["/favicon.ico"];
 }
 
+/// 支持的请求方式.
+const ALLOW_METHODS: &str = "GET, HEAD, OPTIONS";
+/// 连接等待队列长度.
+const LISTEN_BACKLOG: i32 = 1024;
+/// 接收连接失败后等待时间, 避免文件句柄耗尽时空转.
+const ACCEPT_DELAY: Duration = Duration::from_millis(100);
+
 
 
 pub async fn start(args: Args) -> Result<(), Box<dyn std::error::Error>> {
@@ -24,56 +51,310 @@ pub async fn start(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     let config_path = args.config;
     let local_path = args.scan;
     let log_output = args.log;
+    let cache_size = args.cache_size;
+    let symlink = args.symlink;
+    let listing = args.listing;
 
     // 设置日志配置
-    setting::setting_log(&log_output).await.unwrap();
+    setting::setting_log(&log_output).await?;
 
     // 资源核心初始化
-    let resource = Resource::from(&local_path, &config_path).await;
-    resource.init_config().await;
+    let resource = Resource::from(&local_path, &config_path, cache_size, symlink, listing).await;
+    resource.init_config().await?;
+    resource.init_index().await;
+    resource.init_files().await;
+    resource.watch();
     *RESOURCE.write().await = Some(resource);
 
-    // 创建socket
-    let socket_address = SocketAddr::from(([0, 0, 0, 0], port));
+    // 监听地址, 未配置时监听 0.0.0.0
+    let bind_list = match args.bind.is_empty() {
+        true => vec![Bind::Tcp(IpAddr::from([0, 0, 0, 0]), None)],
+        false => args.bind.clone()
+    };
+
+    // HTTPS 证书
+    let acceptor = match (&args.tls_cert, &args.tls_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let resolver = Arc::new(CertResolver::from(cert_path, key_path)?);
+            resolver.watch();
+            Some(tls::acceptor(resolver))
+        }
+        _ => None
+    };
 
-    info!("BlueberryBox started on port(s) {} (http) with scan path '{}'", port, local_path);
+    // 绑定全部地址, 任一失败则启动失败
+    let mut handle_list = vec![];
+    let mut address_list = vec![];
+    let mut redirect_list = HashSet::new();
+    for bind in &bind_list {
+        match bind {
+            Bind::Tcp(ip, value) => {
+                let address = SocketAddr::new(*ip, value.unwrap_or(port));
+                let listener = bind_tcp(address).map_err(|e| format!("Bind '{}' error: {}", address, e))?;
+                address_list.push(format!("{} ({})", address, if acceptor.is_some() { "https" } else { "http" }));
+                handle_list.push(tokio::spawn(serve_tcp(listener, acceptor.clone())));
+                // 同一 IP 只监听一个跳转端口
+                if let Some(redirect_port) = args.redirect_port.filter(|_| redirect_list.insert(*ip)) {
+                    let redirect_address = SocketAddr::new(*ip, redirect_port);
+                    let listener = bind_tcp(redirect_address).map_err(|e| format!("Bind '{}' error: {}", redirect_address, e))?;
+                    address_list.push(format!("{} (http redirect)", redirect_address));
+                    handle_list.push(tokio::spawn(serve_redirect(listener, address.port())));
+                }
+            }
+            #[cfg(unix)]
+            Bind::Unix(path) => {
+                let listener = bind_unix(path).map_err(|e| format!("Bind '{}' error: {}", bind, e))?;
+                address_list.push(format!("{} (http)", bind));
+                handle_list.push(tokio::spawn(serve_unix(listener)));
+            }
+            #[cfg(not(unix))]
+            Bind::Unix(_) => return Err(format!("Bind '{}' error: unix socket is not supported", bind).into())
+        }
+    }
 
-    // 启动服务
-    if let Err(e) = Server::bind(&socket_address).serve(make_service_fn(|_| async {
-        Ok::<_, Infallible>(service_fn(request_handle))
-    })).await {
-        error!("BlueberryBox Server Error: {}", e);
+    info!("BlueberryBox started on {} with scan path '{}'", address_list.join(", "), local_path);
+    for it in handle_list {
+        let _ = it.await;
     }
     Ok(())
 }
 
-async fn request_handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+/// 绑定 TCP 地址, IPv6 地址只接收 IPv6 连接, 可以和同端口的 IPv4 地址同时监听.
+fn bind_tcp(address: SocketAddr) -> std::io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(address), Type::STREAM, Some(Protocol::TCP))?;
+    if address.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+    socket.set_nonblocking(true)?;
+    TcpListener::from_std(socket.into())
+}
+
+/// 接收 TCP 连接, 配置证书时使用 HTTPS, ALPN 协商 h2 时使用 HTTP/2.
+async fn serve_tcp(listener: TcpListener, acceptor: Option<TlsAcceptor>) {
+    loop {
+        let (stream, remote) = match listener.accept().await {
+            Ok(value) => value,
+            Err(e) => {
+                error!("Accept connection error: {}", e);
+                tokio::time::sleep(ACCEPT_DELAY).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        tokio::spawn(async move {
+            let acceptor = match acceptor {
+                None => return serve_connection(stream, Some(remote), false, false).await,
+                Some(value) => value
+            };
+            let stream = match acceptor.accept(stream).await {
+                Ok(value) => value,
+                Err(e) => {
+                    info!("TLS handshake from {} error: {}", remote, e);
+                    return;
+                }
+            };
+            let h2 = stream.get_ref().1.alpn_protocol() == Some(b"h2");
+            serve_connection(stream, Some(remote), true, h2).await;
+        });
+    }
+}
+
+/// 绑定 Unix 域套接字, 删除遗留的套接字文件.
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> std::io::Result<UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if metadata.file_type().is_socket() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    UnixListener::bind(path)
+}
+
+/// 接收 Unix 域套接字连接.
+#[cfg(unix)]
+async fn serve_unix(listener: UnixListener) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(serve_connection(stream, None, false, false));
+            }
+            Err(e) => {
+                error!("Accept connection error: {}", e);
+                tokio::time::sleep(ACCEPT_DELAY).await;
+            }
+        }
+    }
+}
+
+/// 处理连接上的请求.
+async fn serve_connection<T>(stream: T, remote: Option<SocketAddr>, secure: bool, h2: bool)
+    where T: AsyncRead + AsyncWrite + Unpin + Send + 'static {
+    let mut http = Http::new();
+    if h2 {
+        http.http2_only(true);
+    }
+    let service = service_fn(move |request| request_handle(request, remote, secure));
+    if let Err(e) = http.serve_connection(stream, service).with_upgrades().await {
+        match remote {
+            None => info!("Connection error: {}", e),
+            Some(remote) => info!("Connection from {} error: {}", remote, e)
+        }
+    }
+}
+
+/// 接收 HTTP 连接并跳转到 HTTPS.
+async fn serve_redirect(listener: TcpListener, https_port: u16) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((value, _)) => value,
+            Err(e) => {
+                error!("Accept connection error: {}", e);
+                tokio::time::sleep(ACCEPT_DELAY).await;
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            let service = service_fn(move |request| redirect_handle(request, https_port));
+            let _ = Http::new().serve_connection(stream, service).await;
+        });
+    }
+}
+
+/// HTTP 跳转到 HTTPS.
+async fn redirect_handle(request: Request<Body>, https_port: u16) -> Result<Response<Body>, Infallible> {
     let mut response = Response::new(Body::empty());
+    let host = request.headers().get(HOST).and_then(|it| it.to_str().ok())
+        .and_then(|it| it.parse::<Authority>().ok());
+    let host = match host {
+        None => {
+            *response.status_mut() = StatusCode::BAD_REQUEST;
+            return Ok(response);
+        }
+        Some(value) => value
+    };
+    let path = request.uri().path_and_query().map(|it| it.as_str()).unwrap_or("/");
+    let location = match https_port {
+        443 => format!("https://{}{}", host.host(), path),
+        _ => format!("https://{}:{}{}", host.host(), https_port, path)
+    };
+    match HeaderValue::from_str(&location) {
+        Ok(value) => {
+            *response.status_mut() = StatusCode::MOVED_PERMANENTLY;
+            response.headers_mut().insert(LOCATION, value);
+        }
+        Err(_) => *response.status_mut() = StatusCode::BAD_REQUEST
+    }
+    Ok(response)
+}
 
-    // 过滤非Get 请求方式
-    if request.method() != &Method::GET {
-        *response.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
-        return Ok(response);
+async fn request_handle(request: Request<Body>, remote: Option<SocketAddr>, secure: bool) -> Result<Response<Body>, Infallible> {
+    // 反向代理路径, 支持任意请求方式
+    let proxy = match &*RESOURCE.read().await {
+        None => None,
+        Some(resource) => match resource.find_proxy(request.uri().path()).await {
+            Ok(value) => value,
+            Err(e) => return Ok(response_error(request.method(), request.uri().path(), e))
+        }
+    };
+    if let Some(proxy) = proxy {
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let proto = if secure { "https" } else { "http" };
+        return Ok(match proxy::forward(request, &proxy, remote, proto).await {
+            Ok(response) => response,
+            Err(e) => response_error(&method, &path, e)
+        });
+    }
+
+    Ok(match response_request(&request).await {
+        Ok(response) => response,
+        Err(e) => response_error(request.method(), request.uri().path(), e)
+    })
+}
+
+/// 错误响应, 4xx 记录警告, 5xx 记录错误.
+fn response_error(method: &Method, path: &str, e: Error) -> Response<Body> {
+    let status = e.status();
+    if status.is_server_error() {
+        error!("{} {} error: {}", method, path, e);
+    } else {
+        warn!("{} {} {}: {}", method, path, status.as_u16(), e);
+    }
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// 处理请求.
+async fn response_request(request: &Request<Body>) -> Result<Response<Body>, Error> {
+    let mut response = Response::new(Body::empty());
+
+    // 过滤非 GET / HEAD 请求方式
+    match *request.method() {
+        Method::GET | Method::HEAD => {}
+        Method::OPTIONS => {
+            *response.status_mut() = StatusCode::NO_CONTENT;
+            response.headers_mut().insert(ALLOW, HeaderValue::from_static(ALLOW_METHODS));
+            return Ok(response);
+        }
+        _ => {
+            *response.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+            response.headers_mut().insert(ALLOW, HeaderValue::from_static(ALLOW_METHODS));
+            return Ok(response);
+        }
     }
 
     // 如果资源没有准备就绪
     let resource = &*RESOURCE.read().await;
-    if resource.is_none() {
+    let resource = match resource {
+        None => {
+            *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            return Ok(response);
+        }
+        Some(value) => value
+    };
+
+    // 如果静态资源存在
+    let accept_encoding = request.headers().get(ACCEPT_ENCODING).and_then(|it| it.to_str().ok());
+    if let Some(value) = resource.is_static_file(request.uri().path(), accept_encoding).await? {
+        if let Ok(value) = HeaderValue::from_str(&resource.cache_control(request.uri().path(), true).await) {
+            response.headers_mut().insert(CACHE_CONTROL, value);
+        }
+        response_file(request, &mut response, &value).await?;
         return Ok(response);
     }
-    let resource = resource.as_ref().unwrap();
 
-    // 如果静态资源不存在
-    match resource.is_static_file(request.uri().path()).await {
-        None => {}
-        Some(value) => {
-            let file = File::open(&value).await.unwrap();
-            *response.body_mut() = Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
-            let extension = match &value.as_path().extension().and_then(OsStr::to_str) {
-                None => "",
-                Some(value) => value
+
+    // 如果是目录, 补全末尾斜杠或输出目录列表
+    if let Some(directory) = resource.is_directory(request.uri().path()).await {
+        let path = request.uri().path();
+        if !path.ends_with('/') && (directory.index || resource.listing) {
+            // 使用规范化路径, 避免 //host 形式的跳转地址指向其它站点
+            let location = match request.uri().query() {
+                None => format!("/{}/", encode_url(&directory.uri)),
+                Some(query) => format!("/{}/?{}", encode_url(&directory.uri), query)
             };
-            response_content_type(response.headers_mut(), extension);
+            *response.status_mut() = StatusCode::MOVED_PERMANENTLY;
+            response.headers_mut().insert(LOCATION, location.parse()?);
+            return Ok(response);
+        }
+        if path.ends_with('/') && !directory.index && resource.listing {
+            let entry_list = resource.list_directory(&directory).await;
+            response.headers_mut().insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+            let accept = request.headers().get(ACCEPT).and_then(|it| it.to_str().ok());
+            let content = if listing::is_json(accept, request.uri().query()) {
+                response_content_type(response.headers_mut(), &resource.content_type("json").await);
+                listing::to_json(&directory.uri, &entry_list)
+            } else {
+                response_content_type(response.headers_mut(), &resource.content_type("html").await);
+                listing::to_html(&directory.uri, &entry_list)
+            };
+            response_content(request, &mut response, content)?;
             return Ok(response);
         }
     }
@@ -81,42 +362,200 @@ async fn request_handle(request: Request<Body>) -> Result<Response<Body>, Infall
 
     // 如果首页文件存在
     if !EXCLUDE.contains(&request.uri().path()) && resource.is_index().await {
-        *response.body_mut() = Body::from(resource.parse_html_by_config(request.uri().path()).await);
-        response_content_type(response.headers_mut(), "html");
+        let html = resource.parse_html_by_config(request.uri().path(), request.uri().query()).await;
+        if let Ok(value) = HeaderValue::from_str(&resource.cache_control(request.uri().path(), false).await) {
+            response.headers_mut().insert(CACHE_CONTROL, value);
+        }
+        response_content_type(response.headers_mut(), &resource.content_type("html").await);
+        response_content(request, &mut response, html)?;
         return Ok(response);
     }
 
 
     *response.status_mut() = StatusCode::NOT_FOUND;
-    return Ok(response);
+    Ok(response)
+}
+
+/// 输出生成的内容, 协商压缩编码并处理 ETag 条件请求.
+fn response_content(request: &Request<Body>, response: &mut Response<Body>, content: String) -> Result<(), Error> {
+    let encoding = response_encoding(request, response, content.len() as u64);
+    let mut etag = condition::content_etag(content.as_bytes());
+    if let Some(encoding) = encoding {
+        etag = condition::encoded_etag(&etag, encoding.name());
+    }
+    response.headers_mut().insert(ETAG, etag.parse()?);
+    if condition::is_not_modified(request.headers(), &etag, None) {
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        response.headers_mut().remove(CONTENT_TYPE);
+        return Ok(());
+    }
+    match encoding {
+        None => {
+            response.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(content.len()));
+            *response.body_mut() = response_body(request, || Body::from(content));
+        }
+        Some(encoding) => {
+            response.headers_mut().insert(CONTENT_ENCODING, HeaderValue::from_static(encoding.name()));
+            *response.body_mut() = response_body(request, || encoding.encode(Cursor::new(content.into_bytes())));
+        }
+    }
+    Ok(())
 }
 
-async fn response_content_type(header: &mut HeaderMap<HeaderValue>, format: &str) {
-    let format = format.to_lowercase();
-    if format == "txt" {
-        (*header).append("Content-Type", ("text/plan; charset=UTF-8").parse().unwrap());
+/// 输出静态资源文件, 支持单个和多个 Range 请求, 优先使用预压缩文件.
+async fn response_file(request: &Request<Body>, response: &mut Response<Body>, static_file: &StaticFile) -> Result<(), Error> {
+    let (path, precompressed) = match &static_file.encoded {
+        None => (&static_file.path, None),
+        Some((path, encoding)) => (path, Some(*encoding))
+    };
+    let mut file = File::open(path).await?;
+    let metadata = file.metadata().await?;
+    let length = metadata.len();
+    let modified = metadata.modified().ok();
+    response_content_type(response.headers_mut(), &static_file.content_type);
+    let encoding = if static_file.precompressed {
+        response.headers_mut().insert(VARY, HeaderValue::from_static("Accept-Encoding"));
+        None
+    } else {
+        response_encoding(request, response, length)
+    };
+    let mut etag = condition::file_etag(&metadata);
+    if let Some(encoding) = precompressed.or(encoding) {
+        etag = condition::encoded_etag(&etag, encoding.name());
+    }
+    response.headers_mut().insert(ETAG, etag.parse()?);
+    if let Some(modified) = modified {
+        response.headers_mut().insert(LAST_MODIFIED, httpdate::fmt_http_date(modified).parse()?);
     }
-    if format == "xml" || format == "json" {
-        (*header).append("Content-Type", format!("text/{}; charset=UTF-8", format).parse().unwrap());
+    if condition::is_not_modified(request.headers(), &etag, modified) {
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        response.headers_mut().remove(CONTENT_TYPE);
+        return Ok(());
     }
-    if format == "pdf" {
-        (*header).append("Content-Type", format!("application/{}; charset=UTF-8", format).parse().unwrap());
+    if let Some(encoding) = precompressed {
+        response.headers_mut().insert(CONTENT_ENCODING, HeaderValue::from_static(encoding.name()));
     }
-    if format == "xls" || format == "xlsx" {
-        (*header).append("Content-Type", ("application/octet-stream; charset=UTF-8").parse().unwrap());
+    response.headers_mut().insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    let range = match request.headers().get(RANGE).and_then(|it| it.to_str().ok()) {
+        None => ByteRange::Full,
+        Some(value) => {
+            let matched = match request.headers().get(IF_RANGE).and_then(|it| it.to_str().ok()) {
+                None => true,
+                Some(if_range) => condition::if_range(if_range, &etag, modified)
+            };
+            if matched { range::parse_range(value, length) } else { ByteRange::Full }
+        }
+    };
+    match range {
+        ByteRange::Full => match encoding {
+            None => {
+                response.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(length));
+                *response.body_mut() = response_body(request, || Body::wrap_stream(ReaderStream::new(file)));
+            }
+            Some(encoding) => {
+                response.headers_mut().insert(CONTENT_ENCODING, HeaderValue::from_static(encoding.name()));
+                *response.body_mut() = response_body(request, || encoding.encode(BufReader::new(file)));
+            }
+        },
+        ByteRange::Unsatisfiable => {
+            *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+            response.headers_mut().remove(CONTENT_TYPE);
+            response.headers_mut().insert(CONTENT_RANGE, format!("bytes */{}", length).parse()?);
+        }
+        ByteRange::Partial(range_list) if range_list.len() == 1 => {
+            let (start, end) = range_list[0];
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            response.headers_mut().insert(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, length).parse()?);
+            response.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(end - start + 1));
+            if request.method() != Method::HEAD {
+                file.seek(SeekFrom::Start(start)).await?;
+            }
+            *response.body_mut() = response_body(request, || Body::wrap_stream(ReaderStream::new(file.take(end - start + 1))));
+        }
+        ByteRange::Partial(range_list) => {
+            let content_type = match response.headers().get(CONTENT_TYPE).and_then(|it| it.to_str().ok()) {
+                None => "application/octet-stream".to_string(),
+                Some(value) => value.to_string()
+            };
+            let boundary = format!("{:016x}", SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64);
+            let part_list: Vec<(u64, u64, String)> = range_list.into_iter().map(|(start, end)| {
+                (start, end, format!("\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                                     boundary, content_type, start, end, length))
+            }).collect();
+            let end_boundary = format!("\r\n--{}--\r\n", boundary);
+            let content_length = part_list.iter().map(|(start, end, head)| head.len() as u64 + end - start + 1).sum::<u64>()
+                + end_boundary.len() as u64;
+
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            response.headers_mut().insert(CONTENT_TYPE, format!("multipart/byteranges; boundary={}", boundary).parse()?);
+            response.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(content_length));
+            *response.body_mut() = response_body(request, || multipart_body(file, part_list, end_boundary));
+        }
     }
-    if format == "js" || format == "css" || format == "html" {
-        (*header).append("Content-Type", format!("text/{}", format).parse().unwrap());
+    Ok(())
+}
+
+/// 响应内容, HEAD 请求不创建内容流, 响应头与 GET 请求相同.
+fn response_body(request: &Request<Body>, body: impl FnOnce() -> Body) -> Body {
+    if request.method() == Method::HEAD {
+        // 长度未知的空内容, 避免压缩内容的 HEAD 响应输出 Content-Length: 0
+        return Body::channel().1;
     }
-    if format == "png" || format == "jpg" || format == "jpeg" || format == "gif" {
-        (*header).append("Content-Type", format!("image/{}", format).parse().unwrap());
+    body()
+}
+
+/// 多个 Range 的 multipart/byteranges 内容.
+fn multipart_body(mut file: File, part_list: Vec<(u64, u64, String)>, end_boundary: String) -> Body {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let mut buffer = vec![0u8; 64 * 1024];
+        for (start, end, head) in part_list {
+            if sender.send_data(Bytes::from(head)).await.is_err() || file.seek(SeekFrom::Start(start)).await.is_err() {
+                return;
+            }
+            let mut reader = (&mut file).take(end - start + 1);
+            loop {
+                match reader.read(&mut buffer).await {
+                    Ok(0) => break,
+                    Ok(size) => {
+                        if sender.send_data(Bytes::copy_from_slice(&buffer[..size])).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        error!("Read range error: {}", e);
+                        sender.abort();
+                        return;
+                    }
+                }
+            }
+        }
+        let _ = sender.send_data(Bytes::from(end_boundary)).await;
+    });
+    body
+}
+
+/// 协商压缩编码, 可压缩类型添加 Vary, Range 请求和小文件不压缩.
+fn response_encoding(request: &Request<Body>, response: &mut Response<Body>, length: u64) -> Option<Encoding> {
+    let content_type = response.headers().get(CONTENT_TYPE).and_then(|it| it.to_str().ok()).unwrap_or_default();
+    if !compress::is_compressible(content_type) {
+        return None;
     }
-    if format == "mp3" || format == "mpeg" || format == "ogg" || format == "wav" {
-        (*header).append("Content-Type", format!("audio/{}", format).parse().unwrap());
+    response.headers_mut().insert(VARY, HeaderValue::from_static("Accept-Encoding"));
+    if length < COMPRESS_MIN_SIZE || request.headers().contains_key(RANGE) {
+        return None;
     }
-    if format == "mp4" || format == "webm" || format == "ogg" {
-        (*header).append("Content-Type", format!("video/{}", format).parse().unwrap());
+    request.headers().get(ACCEPT_ENCODING).and_then(|it| it.to_str().ok())
+        .and_then(|it| compress::accept_encoding(it, &ENCODING_LIST))
+}
+
+/// 设置内容类型, 禁止浏览器嗅探内容类型.
+fn response_content_type(header: &mut HeaderMap<HeaderValue>, content_type: &str) {
+    if let Ok(value) = HeaderValue::from_str(content_type) {
+        header.insert(CONTENT_TYPE, value);
     }
+    header.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
 }
 
 // if std::path::Path::new(value.as_os_str()).exists() {
@@ -161,3 +600,215 @@ async fn response_content_type(header: &mut HeaderMap<HeaderValue>, format: &str
 //     let full_body = hyper::body::to_bytes(_req.into_body()).await.unwrap();
 //     Ok(Response::new(full_body.into()))
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::SymlinkPolicy;
+    use crate::test_util::{serve, temp_dir, upstream_server, CERT, GLOBAL_LOCK, KEY};
+
+    /// 使用目录初始化全局资源.
+    async fn init_resource(root: &std::path::Path) {
+        let config_path = root.join("config.json");
+        let resource = Resource::from(root.to_str().unwrap(), config_path.to_str().unwrap(), 0, SymlinkPolicy::Root, false).await;
+        resource.init_config().await.unwrap();
+        resource.init_index().await;
+        resource.init_files().await;
+        *RESOURCE.write().await = Some(resource);
+    }
+
+    /// 发送请求.
+    async fn send(method: Method, uri: &str, headers: &[(&str, &str)]) -> Response<Body> {
+        let mut request = Request::builder().method(method).uri(uri);
+        for (key, value) in headers {
+            request = request.header(*key, *value);
+        }
+        request_handle(request.body(Body::empty()).unwrap(), None, false).await.unwrap()
+    }
+
+    fn header(response: &Response<Body>, name: &str) -> Option<String> {
+        response.headers().get(name).map(|it| it.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn head_request() {
+        let _lock = GLOBAL_LOCK.lock().await;
+        let root = temp_dir("head");
+        std::fs::write(root.join("index.html"), "<html><head></head><body></body></html>").unwrap();
+        std::fs::write(root.join("big.txt"), "a".repeat(4096)).unwrap();
+        std::fs::write(root.join("small.txt"), "small").unwrap();
+        init_resource(&root).await;
+        let address = serve(|request| async move { request_handle(request, None, false).await.unwrap() }).await;
+
+        // 经过连接发送, 比较 GET 和 HEAD 的响应头
+        let client = hyper::Client::new();
+        for (uri, headers) in [
+            ("/big.txt", vec![("accept-encoding", "gzip")]),
+            ("/big.txt", vec![("range", "bytes=0-1,4-5")]),
+            ("/big.txt", vec![("range", "bytes=0-9")]),
+            ("/small.txt", vec![("accept-encoding", "gzip")]),
+            ("/index", vec![]),
+        ] {
+            let mut header_list = vec![];
+            for method in [Method::GET, Method::HEAD] {
+                let mut request = Request::builder().method(method.clone()).uri(format!("http://{}{}", address, uri));
+                for (key, value) in &headers {
+                    request = request.header(*key, *value);
+                }
+                let response = client.request(request.body(Body::empty()).unwrap()).await.unwrap();
+                let mut response_headers = response.headers().clone();
+                // 日期, multipart 分隔符每次请求不同, HEAD 响应不使用分块传输
+                response_headers.remove("date");
+                response_headers.remove(CONTENT_TYPE);
+                response_headers.remove("transfer-encoding");
+                let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+                assert_eq!(body.is_empty(), method == Method::HEAD, "{} {}", method, uri);
+                header_list.push(response_headers);
+            }
+            assert_eq!(header_list[0], header_list[1], "{}", uri);
+        }
+        let response = send(Method::HEAD, "/big.txt", &[("accept-encoding", "gzip")]).await;
+        assert_eq!(header(&response, "content-encoding").as_deref(), Some("gzip"));
+        assert_eq!(header(&response, "content-length"), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn options_request() {
+        let _lock = GLOBAL_LOCK.lock().await;
+        let root = temp_dir("options");
+        init_resource(&root).await;
+
+        let response = send(Method::OPTIONS, "/any", &[]).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(header(&response, "allow").as_deref(), Some(ALLOW_METHODS));
+        let response = send(Method::POST, "/any", &[]).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(header(&response, "allow").as_deref(), Some(ALLOW_METHODS));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn proxy_dot_segment() {
+        let _lock = GLOBAL_LOCK.lock().await;
+        let root = temp_dir("proxy-dot");
+        let upstream = upstream_server().await;
+        std::fs::write(root.join("config.json"), format!(r#"{{"proxy": [{{"path": "/api", "upstream": "http://{}/v1", "strip": true}}]}}"#, upstream)).unwrap();
+        std::fs::write(root.join("index.html"), "<html><head></head><body></body></html>").unwrap();
+        init_resource(&root).await;
+
+        let response = send(Method::GET, "/api/user", &[]).await;
+        assert_eq!(header(&response, "echo-uri"), Some("/v1/user".to_string()));
+        for uri in ["/api/../admin/secret", "/api/../index.html", "/api/%2E%2E/index.html", "/./api/user"] {
+            assert_eq!(send(Method::GET, uri, &[]).await.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn proxy_over_h2() {
+        use std::convert::TryFrom;
+        use tokio_rustls::TlsConnector;
+        use tokio_rustls::rustls::{Certificate, ClientConfig, RootCertStore, ServerName};
+
+        let _lock = GLOBAL_LOCK.lock().await;
+        let root = temp_dir("h2-proxy");
+        let upstream = upstream_server().await;
+        std::fs::write(root.join("config.json"), format!(r#"{{"proxy": [{{"path": "/api", "upstream": "http://{}"}}]}}"#, upstream)).unwrap();
+        std::fs::write(root.join("cert.pem"), CERT).unwrap();
+        std::fs::write(root.join("key.pem"), KEY).unwrap();
+        init_resource(&root).await;
+        let resolver = CertResolver::from(root.join("cert.pem").to_str().unwrap(), root.join("key.pem").to_str().unwrap()).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve_tcp(listener, Some(tls::acceptor(Arc::new(resolver)))));
+
+        // 信任测试证书, ALPN 只协商 h2
+        let mut root_store = RootCertStore::empty();
+        for it in rustls_pemfile::certs(&mut CERT.as_bytes()).unwrap() {
+            root_store.add(&Certificate(it)).unwrap();
+        }
+        let mut config = ClientConfig::builder().with_safe_defaults().with_root_certificates(root_store).with_no_client_auth();
+        config.alpn_protocols = vec![b"h2".to_vec()];
+        let stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        let stream = TlsConnector::from(Arc::new(config)).connect(ServerName::try_from("localhost").unwrap(), stream).await.unwrap();
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+        let (mut sender, connection) = hyper::client::conn::Builder::new().http2_only(true).handshake(stream).await.unwrap();
+        tokio::spawn(connection);
+
+        let request = Request::builder().method(Method::POST).uri(format!("https://localhost:{}/api/user", address.port()))
+            .body(Body::from("hello")).unwrap();
+        let response = sender.send_request(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, "echo-uri").as_deref(), Some("/api/user"));
+        assert_eq!(header(&response, "echo-x-forwarded-proto").as_deref(), Some("https"));
+        assert_eq!(header(&response, "echo-x-forwarded-host"), Some(format!("localhost:{}", address.port())));
+        assert_eq!(header(&response, "echo-x-forwarded-for").as_deref(), Some("127.0.0.1"));
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "hello");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn bind_dual_stack() {
+        // 环境不支持 IPv6 时跳过
+        let ipv6 = match bind_tcp("[::]:0".parse().unwrap()) {
+            Ok(value) => value,
+            Err(_) => return
+        };
+        let port = ipv6.local_addr().unwrap().port();
+        let ipv4 = bind_tcp(SocketAddr::new(IpAddr::from([0, 0, 0, 0]), port)).unwrap();
+        assert_eq!(ipv4.local_addr().unwrap().port(), port);
+        assert!(bind_tcp(SocketAddr::new(IpAddr::from([0, 0, 0, 0]), port)).is_err());
+    }
+
+    #[tokio::test]
+    async fn directory_redirect() {
+        let _lock = GLOBAL_LOCK.lock().await;
+        let root = temp_dir("redirect");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::create_dir_all(root.join("a b")).unwrap();
+        std::fs::write(root.join("docs/index.html"), "docs").unwrap();
+        std::fs::write(root.join("a b/index.html"), "a b").unwrap();
+        init_resource(&root).await;
+
+        let location = |response: Response<Body>| (response.status(), header(&response, "location"));
+        assert_eq!(location(send(Method::GET, "/docs", &[]).await), (StatusCode::MOVED_PERMANENTLY, Some("/docs/".to_string())));
+        assert_eq!(location(send(Method::GET, "//docs", &[]).await), (StatusCode::MOVED_PERMANENTLY, Some("/docs/".to_string())));
+        assert_eq!(location(send(Method::GET, "//docs?a=1", &[]).await), (StatusCode::MOVED_PERMANENTLY, Some("/docs/?a=1".to_string())));
+        assert_eq!(location(send(Method::GET, "/a%20b", &[]).await), (StatusCode::MOVED_PERMANENTLY, Some("/a%20b/".to_string())));
+        let response = send(Method::GET, "/docs/", &[]).await;
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "docs");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn precompressed_file() {
+        let _lock = GLOBAL_LOCK.lock().await;
+        let root = temp_dir("precompressed");
+        std::fs::create_dir_all(root.join("js")).unwrap();
+        std::fs::write(root.join("js/app.js"), "console.log(1)").unwrap();
+        std::fs::write(root.join("js/app.js.br"), "brotli").unwrap();
+        std::fs::write(root.join("js/app.js.gz"), "gzip").unwrap();
+        init_resource(&root).await;
+
+        // 按 Accept-Encoding 选择预压缩文件, 内容类型使用原文件扩展名
+        let response = send(Method::GET, "/js/app.js", &[("accept-encoding", "gzip, br")]).await;
+        assert_eq!(header(&response, "content-type").as_deref(), Some("text/javascript; charset=utf-8"));
+        assert_eq!(header(&response, "content-encoding").as_deref(), Some("br"));
+        assert_eq!(header(&response, "vary").as_deref(), Some("Accept-Encoding"));
+        assert_eq!(header(&response, "content-length").as_deref(), Some("6"));
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "brotli");
+
+        let response = send(Method::GET, "/js/app.js", &[("accept-encoding", "gzip")]).await;
+        assert_eq!(header(&response, "content-type").as_deref(), Some("text/javascript; charset=utf-8"));
+        assert_eq!(header(&response, "content-encoding").as_deref(), Some("gzip"));
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "gzip");
+
+        // 不支持压缩时输出原文件
+        let response = send(Method::GET, "/js/app.js", &[]).await;
+        assert_eq!(header(&response, "content-encoding"), None);
+        assert_eq!(header(&response, "vary").as_deref(), Some("Accept-Encoding"));
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "console.log(1)");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,7 +1,4 @@
 use std::env;
-use std::fmt::Write;
-use std::path::PathBuf;
-use log::info;
 use tokio::io::AsyncWriteExt;
 use crate::core::FileInfo;
 
@@ -9,12 +6,12 @@ use crate::core::FileInfo;
 pub async fn setting_log(output: &str) -> Result<(), Box<dyn std::error::Error>> {
     let file_info = FileInfo::from_vec(vec![output, "blueberry_box.log"]).await;
     if !file_info.directory_exist() {
-        file_info.create_directory().await;
+        file_info.create_directory().await?;
     }
     let log_output = file_info.path_buf.as_path();
     fern::Dispatch::new()
         // Perform allocation-free log formatting
-        .format(|out, message, record| {
+        .format(|out, message, _record| {
             out.finish(format_args!(
                 "{}",
                 message
@@ -31,6 +28,7 @@ pub async fn setting_log(output: &str) -> Result<(), Box<dyn std::error::Error>>
         .level(log::LevelFilter::Debug)
         // - and per-module overrides
         .level_for("hyper", log::LevelFilter::Info)
+        .level_for("rustls", log::LevelFilter::Info)
         // Output to stdout, files, and other Dispatch configurations
         .chain(std::io::stdout())
         .chain(fern::log_file(log_output)?)
@@ -39,41 +37,64 @@ pub async fn setting_log(output: &str) -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+/// output config template and doc to current directory, skip existing files
 pub async fn output_config_json() -> Result<(), Box<dyn std::error::Error>> {
-    let json = r##"[
-    {
-        "path":"",
-        "metas":[
-            {
-                "name":"",
-                "content":""
-            }
-        ],
-        "title":"",
-        "heads":[
-        ]
-    }
-]"##;
+    let json = r##"{
+    "seo":[
+        {
+            "path":"",
+            "metas":[
+                {
+                    "name":"",
+                    "content":""
+                }
+            ],
+            "links":[
+            ],
+            "title":"",
+            "heads":[
+            ]
+        }
+    ],
+    "cache":[
+    ],
+    "mime":{
+    },
+    "proxy":[
+    ]
+}"##;
 
-    let json_doc = r##"path supports three rules: pre:// tail:// regular://; example pre://index/home
-metas Head SEO Resource Node
-title Web Page title
-heads Custom head"##;
-    let mut output_json = PathBuf::from(env::current_dir()?);
+    let json_doc = r##"config file is {"seo": [...], "cache": [...], "mime": {...}, "proxy": [...]}, a plain array is the seo list
+seo.path supports four rules: exact:// pre:// tail:// regular://, empty path matches all; priority exact > longest pre > longest tail > regular > empty; example pre://index/home regular://^/item/\d+$
+seo.path params exact://product/:id or regular://^/product/(?P<id>\d+)$, use {id} and {query.name} in title / metas content / links href / heads
+seo.metas Head SEO Resource Node, key by name / property / http-equiv / itemprop / charset with content
+seo.links Head link Node, rel / href / hreflang / type
+seo.title Web Page title
+seo.remote Remote SEO json http url, example http://api.local/seo?path={path}, response title / metas / links / heads override this rule, fallback to this rule on error
+seo.remote_timeout Remote SEO timeout milliseconds, default 3000
+seo.heads Custom head, output as raw html without escaping
+cache.path Same rules as seo.path
+cache.control Cache-Control header, default hashed file like app.3f9a1c.js public, max-age=31536000, immutable, others no-cache
+mime Custom Content-Type by extension, example {"log": "text/plain"}, override built-in types, text types append charset=utf-8, unknown is application/octet-stream
+proxy.path Path prefix matched by segment, /api matches /api and /api/user but not /apis, longest prefix wins, any request method, WebSocket upgrade is forwarded, paths with . or .. segments are rejected with 400
+proxy.upstream Upstream http url, example http://127.0.0.1:8080 or http://127.0.0.1:8080/v1
+proxy.strip Remove path prefix before forwarding, default false
+proxy.timeout Upstream response timeout milliseconds, default 30000, 502 on upstream error and 504 on timeout"##;
+    let mut output_json = env::current_dir()?;
     output_json.push("config.json");
-    let mut output_json_doc = PathBuf::from(env::current_dir()?);
+    let mut output_json_doc = env::current_dir()?;
     output_json_doc.push("config_doc.txt");
 
     if !std::path::Path::new(&output_json).exists() {
         let mut file = tokio::fs::File::create(output_json).await?;
         file.write_all(json.as_bytes()).await?;
-        info!("config.json out success!")
+        println!("config.json out success!")
     }
 
     if !std::path::Path::new(&output_json_doc).exists() {
         let mut file = tokio::fs::File::create(output_json_doc).await?;
         file.write_all(json_doc.as_bytes()).await?;
-        info!("config_doc.txt out success!")
+        println!("config_doc.txt out success!")
     }
 
     Ok(())
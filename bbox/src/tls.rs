@@ -0,0 +1,145 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use log::{error, info};
+use notify::RecursiveMode;
+use rustls_pemfile::Item;
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio_rustls::rustls::sign::{self, CertifiedKey};
+use tokio_rustls::TlsAcceptor;
+use crate::error::Error;
+use crate::watch;
+
+/// 证书文件变化后等待时间, 证书和私钥通常先后写入.
+const RELOAD_DELAY: Duration = Duration::from_millis(500);
+
+/// 证书解析, 支持热加载证书和私钥.
+pub struct CertResolver {
+    /// 证书路径.
+    cert_path: String,
+    /// 私钥路径.
+    key_path: String,
+    /// 当前证书.
+    certified_key: RwLock<Arc<CertifiedKey>>,
+}
+
+impl CertResolver {
+    /// 加载证书和私钥.
+    pub fn from(cert_path: &str, key_path: &str) -> Result<CertResolver, Error> {
+        Ok(CertResolver {
+            cert_path: cert_path.to_string(),
+            key_path: key_path.to_string(),
+            certified_key: RwLock::new(Arc::new(load_certified_key(cert_path, key_path)?)),
+        })
+    }
+
+    /// 重新加载证书和私钥, 失败时保留原证书.
+    pub fn reload(&self) -> Result<(), Error> {
+        let certified_key = load_certified_key(&self.cert_path, &self.key_path)?;
+        if let Ok(mut value) = self.certified_key.write() {
+            *value = Arc::new(certified_key);
+        }
+        Ok(())
+    }
+
+    /// 监听证书和私钥文件变化.
+    pub fn watch(self: &Arc<Self>) {
+        let resolver = self.clone();
+        let mut path_list = vec![];
+        let mut file_list = vec![];
+        for path in [&self.cert_path, &self.key_path] {
+            match watch::file_path(path) {
+                Some((directory, file)) => {
+                    if !path_list.iter().any(|(it, _)| it == &directory) {
+                        path_list.push((directory, RecursiveMode::NonRecursive));
+                    }
+                    file_list.push(file);
+                }
+                None => error!("Watch '{}' error: invalid path", path)
+            }
+        }
+        watch::spawn(path_list, move |path, _, receiver| {
+            if !file_list.contains(&path) {
+                return;
+            }
+            // 合并证书和私钥先后写入的事件
+            std::thread::sleep(RELOAD_DELAY);
+            while receiver.try_recv().is_ok() {}
+            match resolver.reload() {
+                Ok(_) => info!("Reload TLS certificate '{}'", resolver.cert_path),
+                Err(e) => error!("{}", e)
+            }
+        });
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        self.certified_key.read().ok().map(|it| it.clone())
+    }
+}
+
+/// 创建 TLS 接收器, ALPN 优先协商 HTTP/2.
+pub fn acceptor(resolver: Arc<CertResolver>) -> TlsAcceptor {
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    TlsAcceptor::from(Arc::new(config))
+}
+
+/// 读取 PEM 格式证书链和私钥, 私钥支持 PKCS#8 / RSA / EC.
+fn load_certified_key(cert_path: &str, key_path: &str) -> Result<CertifiedKey, Error> {
+    let tls_error = |path: &str, message: String| Error::Tls { path: path.to_string(), message };
+
+    let file = File::open(cert_path).map_err(|e| tls_error(cert_path, e.to_string()))?;
+    let cert_list = rustls_pemfile::certs(&mut BufReader::new(file)).map_err(|e| tls_error(cert_path, e.to_string()))?;
+    if cert_list.is_empty() {
+        return Err(tls_error(cert_path, "no certificate found".to_string()));
+    }
+
+    let file = File::open(key_path).map_err(|e| tls_error(key_path, e.to_string()))?;
+    let mut reader = BufReader::new(file);
+    let key = loop {
+        match rustls_pemfile::read_one(&mut reader).map_err(|e| tls_error(key_path, e.to_string()))? {
+            Some(Item::PKCS8Key(value)) | Some(Item::RSAKey(value)) | Some(Item::ECKey(value)) => break value,
+            Some(_) => continue,
+            None => return Err(tls_error(key_path, "no private key found".to_string()))
+        }
+    };
+    let key = sign::any_supported_type(&PrivateKey(key)).map_err(|e| tls_error(key_path, e.to_string()))?;
+    Ok(CertifiedKey::new(cert_list.into_iter().map(Certificate).collect(), key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{temp_dir, CERT, KEY};
+
+    #[test]
+    fn load_and_reload() {
+        let root = temp_dir("tls");
+        let cert_path = root.join("cert.pem");
+        let key_path = root.join("key.pem");
+        let (cert_path, key_path) = (cert_path.to_str().unwrap(), key_path.to_str().unwrap());
+        std::fs::write(cert_path, CERT).unwrap();
+        std::fs::write(key_path, KEY).unwrap();
+
+        let resolver = CertResolver::from(cert_path, key_path).unwrap();
+        assert_eq!(resolver.certified_key.read().unwrap().cert.len(), 1);
+
+        // 私钥损坏时保留原证书
+        std::fs::write(key_path, CERT).unwrap();
+        let message = resolver.reload().unwrap_err().to_string();
+        assert!(message.contains(key_path) && message.contains("no private key found"), "{}", message);
+        assert_eq!(resolver.certified_key.read().unwrap().cert.len(), 1);
+
+        std::fs::write(cert_path, "").unwrap();
+        let message = CertResolver::from(cert_path, key_path).err().unwrap().to_string();
+        assert!(message.contains(cert_path) && message.contains("no certificate found"), "{}", message);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use log::error;
+use notify::{raw_watcher, RawEvent, RecursiveMode, Watcher};
+use notify::op::Op;
+
+/// 文件所在目录和完整路径, 监听所在目录以便捕获替换文件的写入.
+pub fn file_path(path: &str) -> Option<(PathBuf, PathBuf)> {
+    let path = Path::new(path);
+    let directory = match path.parent() {
+        Some(value) if !value.as_os_str().is_empty() => value,
+        _ => Path::new(".")
+    };
+    let directory = std::fs::canonicalize(directory).ok()?;
+    let file = directory.join(path.file_name()?);
+    Some((directory, file))
+}
+
+/// 在新线程中监听路径并依次处理事件, 处理时可以从接收器中合并后续事件.
+pub fn spawn<F>(path_list: Vec<(PathBuf, RecursiveMode)>, mut handle: F)
+    where F: FnMut(PathBuf, Op, &Receiver<RawEvent>) + Send + 'static {
+    std::thread::spawn(move || {
+        let (tx, rx) = channel();
+        let mut watcher = match raw_watcher(tx) {
+            Ok(value) => value,
+            Err(e) => {
+                error!("Watcher create error: {}", e);
+                return;
+            }
+        };
+        for (path, mode) in &path_list {
+            if let Err(e) = watcher.watch(path, *mode) {
+                error!("Watch '{}' error: {}", path.display(), e);
+            }
+        }
+        while let Ok(event) = rx.recv() {
+            if let RawEvent { path: Some(path), op: Ok(op), .. } = event {
+                handle(path, op, &rx);
+            }
+        }
+    });
+}